@@ -1,15 +1,161 @@
 use log::*;
+use mask::*;
 use object::*;
 use smt::*;
+use tolerance::*;
 use trace::*;
 use utils::*;
 
 use rayon::prelude::*;
 use std::cmp::min;
-use std::collections::HashMap;
+use std::collections::HashSet;
 
-fn is_same_event(evt1: &Event, evt2: &Event) -> String {
+#[derive(Clone, Debug)]
+pub struct ComparisonResult {
+    pub deviation: String,
+    pub timestamp: u64,
+    // number of floating point values that only matched within tolerance
+    pub tolerated: u64,
+    // number of deviations ignored because of the nondeterminism mask
+    pub suppressed: u64,
+    // injection of the faulty trace the deviation is attributed to
+    pub injection: Option<usize>,
+    // instruction ID and trace position of the first deviating event of the
+    // faulty trace
+    pub id: String,
+    pub inst: Option<usize>,
+    // field path accessed by the first deviating event
+    pub field: String,
+}
+
+// Activation of a single injection of a faulty trace: the first deviation
+// that is attributed to it and its latency in timestamp units.
+#[derive(Clone, Debug)]
+pub struct InjectionReport {
+    pub timestamp: u64,
+    pub instruction: u32,
+    pub deviation: String,
+    pub latency: Option<u64>,
+}
+
+impl ComparisonResult {
+    fn new(deviation: &str, timestamp: u64, tolerated: u64, suppressed: u64) -> ComparisonResult {
+        ComparisonResult {
+            deviation: String::from(deviation),
+            timestamp,
+            tolerated,
+            suppressed,
+            injection: None,
+            id: String::new(),
+            inst: None,
+            field: String::new(),
+        }
+    }
+
+    fn attributed(mut self, tr: &SymbolicTrace, evt: Option<&&Event>) -> ComparisonResult {
+        self.injection = evt.and_then(|e| tr.get_preceding_injection(get_long(&e.timestamp)));
+        self.id = evt.map_or(String::new(), |e| e.id.clone());
+        self.inst = evt.map(|e| e.inst);
+        self.field = evt.map_or(String::new(), |e| e.field.clone());
+        self
+    }
+}
+
+impl InjectionReport {
+    pub fn is_activated(&self) -> bool {
+        self.latency.is_some()
+    }
+}
+
+// Result of the comparison of two aligned events: the first deviation in the
+// order the fields are compared ("no-dev", "tol-match", "data-dev",
+// "addr-dev" or "control-dev") and all fields the events differ in. Events
+// that can't be aligned (another kind of event or call) have no fields.
+#[derive(Clone, Debug)]
+pub struct EventDiff {
+    pub deviation: String,
+    pub fields: Option<HashSet<MaskField>>,
+    tolerated: bool,
+}
+
+impl EventDiff {
+    fn new() -> EventDiff {
+        EventDiff {
+            deviation: String::new(),
+            fields: Some(HashSet::new()),
+            tolerated: false,
+        }
+    }
+
+    fn unaligned() -> EventDiff {
+        EventDiff {
+            deviation: String::from("control-dev"),
+            fields: None,
+            tolerated: false,
+        }
+    }
+
+    pub fn is_deviation(&self) -> bool {
+        self.deviation != "no-dev" && self.deviation != "tol-match"
+    }
+
+    fn add(&mut self, deviation: &str, field: MaskField) {
+        if self.deviation.is_empty() {
+            self.deviation = String::from(deviation);
+        }
+        if let Some(f) = self.fields.as_mut() {
+            f.insert(field);
+        }
+    }
+
+    // `field` overrides the field of a value or pointer, e.g. for arguments.
+    fn add_data(
+        &mut self,
+        data1: &EventData,
+        data2: &EventData,
+        ev: &Event,
+        tol: &Tolerance,
+        field: Option<MaskField>,
+    ) {
+        let is_value = matches!(
+            (data1, data2),
+            (EventData::Value { .. }, EventData::Value { .. })
+        );
+        match &*is_same_event_data(data1, data2, ev, tol) {
+            "Tol" => self.tolerated = true,
+            "Data" => self.add(
+                "data-dev",
+                field.unwrap_or(if is_value {
+                    MaskField::Value
+                } else {
+                    MaskField::Pointer
+                }),
+            ),
+            "Addr" => self.add("addr-dev", field.unwrap_or(MaskField::Pointer)),
+            _ => {}
+        }
+    }
+}
+
+// Options of the comparison that aren't value tolerances.
+#[derive(Clone, Debug, Default)]
+pub struct CompareOptions {
+    // events also have to be executed in the same function and frame depth
+    pub call_context: bool,
+}
+
+pub fn get_event_diff(
+    evt1: &Event,
+    evt2: &Event,
+    tol: &Tolerance,
+    opts: &CompareOptions,
+) -> EventDiff {
     use trace::EventType::*;
+    // the same instruction executed in another call context
+    if opts.call_context && (evt1.depth != evt2.depth || evt1.func != evt2.func) {
+        return EventDiff::unaligned();
+    }
+    let mut res = EventDiff::new();
     match (&evt1.data, &evt2.data) {
         (
             &Read {
@@ -24,17 +170,8 @@ fn is_same_event(evt1: &Event, evt2: &Event) -> String {
                 offset: ref off2,
                 ..
             },
-        ) => {
-            let res = is_same_event_data(v1, v2);
-            if res == "Data" {
-                return String::from("data-dev");
-            } else if res == "Addr" {
-                return String::from("addr-dev");
-            } else if !(is_same_object(o1, o2) && off1 == off2) {
-                return String::from("addr-dev");
-            }
-        }
-        (
+        )
+        | (
             &Write {
                 value: ref v1,
                 object: ref o1,
@@ -48,30 +185,22 @@ fn is_same_event(evt1: &Event, evt2: &Event) -> String {
                 ..
             },
         ) => {
-            let res = is_same_event_data(v1, v2);
-            if res == "Data" {
-                return String::from("data-dev");
-            } else if res == "Addr" {
-                return String::from("addr-dev");
-            } else if !(is_same_object(o1, o2) && off1 == off2) {
-                return String::from("addr-dev");
+            res.add_data(v1, v2, evt1, tol, None);
+            if !(is_same_object(o1, o2) && off1 == off2) {
+                res.add("addr-dev", MaskField::Pointer);
             }
         }
         (&Branch { target: ref t1 }, &Branch { target: ref t2 }) => {
             if t1 != t2 {
-                return String::from("control-dev");
+                res.add("control-dev", MaskField::Branch);
             }
         }
         (&Fork { .. }, &Fork { .. }) => {}
         (&Join { .. }, &Join { .. }) => {}
-        (&Lock { mutex: ref mtx1 }, &Lock { mutex: ref mtx2 }) => {
+        (&Lock { mutex: ref mtx1 }, &Lock { mutex: ref mtx2 })
+        | (&Unlock { mutex: ref mtx1 }, &Unlock { mutex: ref mtx2 }) => {
             if !is_same_object(mtx1, mtx2) {
-                return String::from("addr-dev");
-            }
-        }
-        (&Unlock { mutex: ref mtx1 }, Unlock { mutex: ref mtx2 }) => {
-            if !is_same_object(mtx1, mtx2) {
-                return String::from("addr-dev");
+                res.add("addr-dev", MaskField::Pointer);
             }
         }
         (
@@ -87,39 +216,37 @@ fn is_same_event(evt1: &Event, evt2: &Event) -> String {
             },
         ) => {
             if nm1 != nm2 {
-                return String::from("control-dev");
+                return EventDiff::unaligned();
             }
-            let res = is_same_event_data(val1, val2);
-            if res == "Data" {
-                return String::from("data-dev");
-            } else if res == "Addr" {
-                return String::from("addr-dev");
-            } else {
-                if args1.len() != args2.len() {
-                    return String::from("control-dev");
-                }
-                for i in 0..min(args1.len(), args2.len()) {
-                    let res = is_same_event_data(&args1[i], &args2[i]);
-                    if res == "Data" {
-                        return String::from("data-dev");
-                    } else if res == "Addr" {
-                        return String::from("addr-dev");
-                    }
+            res.add_data(val1, val2, evt1, tol, None);
+            if args1.len() != args2.len() {
+                if res.deviation.is_empty() {
+                    res.deviation = String::from("control-dev");
                 }
+                res.fields = None;
+                return res;
+            }
+            for (a1, a2) in args1.iter().zip(args2.iter()) {
+                res.add_data(a1, a2, evt1, tol, Some(MaskField::Args));
             }
         }
         _ => {
-            return String::from("control-dev");
+            return EventDiff::unaligned();
         }
     }
-    String::from("no-dev")
+    if res.deviation.is_empty() {
+        res.deviation = String::from(if res.tolerated { "tol-match" } else { "no-dev" });
+    }
+    res
 }
 
-fn is_same_event_data(data1: &EventData, data2: &EventData) -> String {
+fn is_same_event_data(data1: &EventData, data2: &EventData, ev: &Event, tol: &Tolerance) -> String {
     match (data1, data2) {
-        (EventData::Value { target: ref t1 }, EventData::Value { target: ref t2 }) => {
-            if t1 == t2 {
+        (EventData::Value { typed: ref t1, .. }, EventData::Value { typed: ref t2, .. }) => {
+            if t1.is_same(t2) {
                 return String::from("Ident");
+            } else if tol.is_within(ev, t1, t2) {
+                return String::from("Tol");
             } else {
                 return String::from("Data");
             }
@@ -164,26 +291,42 @@ pub fn compare_sequential(tr1: &SymbolicTrace, tr2: &SymbolicTrace) -> bool {
     true
 }
 */
-pub fn compare_naive(tr1: &SymbolicTrace, tr2: &SymbolicTrace) -> String {
+pub fn get_projection<'a>(tr: &'a SymbolicTrace, th: &str) -> Vec<&'a Event> {
+    tr.events.par_iter().filter(|x| x.thread == th).collect()
+}
+
+pub fn compare_naive(
+    tr1: &SymbolicTrace,
+    tr2: &SymbolicTrace,
+    tol: &Tolerance,
+    opts: &CompareOptions,
+    mask: &Mask,
+) -> ComparisonResult {
     let mut tmp = Vec::new();
     for th in tr1.thread_naming.values() {
         tmp.push(th);
     }
     //tmp.sort();
-    let mut res = tmp.par_iter().filter_map(|th| {
-        let test = compare_projection(tr1, tr2, th);
+    let all = tmp.par_iter().map(|th| {
+        let test = compare_projection(tr1, tr2, th, tol, opts, mask);
         info!("Thread {} has been compared...", th);
-        if test.0 != "no-dev" {
-            return Some(test);
-        }
-        return None;
+        return test;
     }).collect::<Vec<_>>();
-    res.sort_by_key(|x| x.1);
+    let tolerated = all.iter().map(|x| x.tolerated).sum();
+    let suppressed = all.iter().map(|x| x.suppressed).sum();
+    let mut res = all.into_iter().filter(|x| x.deviation != "no-dev").collect::<Vec<_>>();
+    res.sort_by_key(|x| x.timestamp);
     if res.len() == 0 {
-        return String::from("no-dev");
+        if tolerated > 0 {
+            return ComparisonResult::new("within-tol", 0, tolerated, suppressed);
+        }
+        return ComparisonResult::new("no-dev", 0, 0, suppressed);
     }
     info!("Done with comparison");
-    return res[0].0.clone();
+    let mut first = res[0].clone();
+    first.tolerated = tolerated;
+    first.suppressed = suppressed;
+    return first;
 /*
     for th in tmp.iter() {
         let mut res = compare_projection(tr1, tr2, th);
@@ -197,67 +340,143 @@ pub fn compare_naive(tr1: &SymbolicTrace, tr2: &SymbolicTrace) -> String {
     String::from("no-dev")
     */
 }
-pub fn compare_projection(tr1: &SymbolicTrace, tr2: &SymbolicTrace, th: &str) -> (String,u64) {
+// Compares the events of a thread of both traces position by position,
+// skipping the events before the first injection of the traces. `visit`
+// gets every compared pair with its diff and returns whether to go on.
+// Returns the number of positions walked and the golden timestamp of the
+// last one.
+pub fn walk_projection<'a, F>(
+    tr1: &SymbolicTrace,
+    tr2: &SymbolicTrace,
+    events1: &[&'a Event],
+    events2: &[&'a Event],
+    tol: &Tolerance,
+    opts: &CompareOptions,
+    mut visit: F,
+) -> (usize, u64)
+where
+    F: FnMut(&'a Event, &'a Event, &EventDiff) -> bool,
+{
     let mut i = 0;
-    let events1 = tr1.events
-        .par_iter()
-        .filter_map(|x| {
-            if x.thread == th {
-                return Some(x.clone());
-            } else {
-                return None;
-            }
-        })
-        .collect::<Vec<_>>();
-    let events2 = &tr2.events
-        .par_iter()
-        .filter_map(|x| {
-            if x.thread == th {
-                return Some(x.clone());
-            } else {
-                return None;
-            }
-        })
-        .collect::<Vec<_>>();
-    
-    if events1.len() == 0 {
-        return (String::from("empty-dev"), 0)
-    }
-    let mut last_timestamp = events1[0].timestamp.clone().parse().unwrap();
+    let mut last_timestamp = events1.first().map_or(0, |e| get_long(&e.timestamp));
+    let (inj1, inj2) = (tr1.get_first_injection(), tr2.get_first_injection());
     while i != min(events1.len(), events2.len()) {
-        last_timestamp = events1[i].timestamp.clone().parse().unwrap();
-        let evt1 = &events1[i];
-        let evt2 = &events2[i];
-        let mut ignore = true;
-        for inj in tr1.injection.iter() {
-            if inj.0 < get_long(&*evt1.timestamp) {
-                ignore = false;
-                break;
-            }
-        }
-        for inj in tr2.injection.iter() {
-            if inj.0 < get_long(&*evt2.timestamp) {
-                ignore = false;
-                break;
-            }
-        }
+        last_timestamp = get_long(&events1[i].timestamp);
+        let evt1 = events1[i];
+        let evt2 = events2[i];
+        // the event is past injection if it follows the earliest injection
+        let ignore = inj1.is_none_or(|ts| ts >= get_long(&evt1.timestamp))
+            && inj2.is_none_or(|ts| ts >= get_long(&evt2.timestamp));
 
         if ignore && !tr1.injection.is_empty() && !tr2.injection.is_empty() {
             i += 1;
             continue;
         }
 
-        let res = is_same_event(evt1, evt2);
-        if res != "no-dev" {
-            return (res,last_timestamp);
+        if !visit(evt1, evt2, &get_event_diff(evt1, evt2, tol, opts)) {
+            break;
         }
         i += 1;
     }
-    if events1.len() != events2.len() {
-        return (String::from("data-dev"), last_timestamp);
+    (i, last_timestamp)
+}
+
+pub fn compare_projection(
+    tr1: &SymbolicTrace,
+    tr2: &SymbolicTrace,
+    th: &str,
+    tol: &Tolerance,
+    opts: &CompareOptions,
+    mask: &Mask,
+) -> ComparisonResult {
+    let mut tolerated = 0;
+    let mut suppressed = 0;
+    let events1 = get_projection(tr1, th);
+    let events2 = get_projection(tr2, th);
+
+    if events1.len() == 0 {
+        return ComparisonResult::new("empty-dev", 0, 0, 0);
+    }
+    let mut deviation = None;
+    let (i, last_timestamp) = walk_projection(
+        tr1,
+        tr2,
+        &events1,
+        &events2,
+        tol,
+        opts,
+        |evt1, evt2, diff| {
+            if diff.deviation == "tol-match" {
+                tolerated += 1;
+            } else if diff.is_deviation() {
+                if mask.covers(&evt1.id, diff) {
+                    suppressed += 1;
+                } else {
+                    deviation = Some((diff.deviation.clone(), evt2));
+                    return false;
+                }
+            }
+            true
+        },
+    );
+    if let Some((dev, evt2)) = deviation {
+        return ComparisonResult::new(&dev, last_timestamp, tolerated, suppressed)
+            .attributed(tr2, Some(&evt2));
+    }
+    // only the common prefix of a truncated trace can be compared
+    if events1.len() != events2.len() && !tr1.truncated && !tr2.truncated {
+        return ComparisonResult::new("data-dev", last_timestamp, tolerated, suppressed)
+            .attributed(tr2, events2.get(i).or(events2.last()));
     }
 
-    (String::from("no-dev"), last_timestamp)
+    ComparisonResult::new("no-dev", last_timestamp, tolerated, suppressed)
+}
+
+// Aligns both traces thread by thread and attributes every deviating event
+// of the faulty trace to the injection preceding it most closely. A thread
+// is only followed up to its first control deviation.
+pub fn get_injection_reports(
+    golden: &SymbolicTrace,
+    faulty: &SymbolicTrace,
+    tol: &Tolerance,
+    opts: &CompareOptions,
+    mask: &Mask,
+) -> Vec<InjectionReport> {
+    let mut res = faulty
+        .injection
+        .iter()
+        .map(|inj| InjectionReport {
+            timestamp: inj.0,
+            instruction: inj.1,
+            deviation: String::from("no-dev"),
+            latency: None,
+        })
+        .collect::<Vec<_>>();
+    for th in golden.thread_naming.values() {
+        let events1 = get_projection(golden, th);
+        let events2 = get_projection(faulty, th);
+        for (evt1, evt2) in events1.iter().zip(events2.iter()) {
+            let ts = get_long(&evt2.timestamp);
+            let inj = match faulty.get_preceding_injection(ts) {
+                Some(i) => i,
+                None => continue,
+            };
+            let diff = get_event_diff(evt1, evt2, tol, opts);
+            if !diff.is_deviation() || mask.covers(&evt1.id, &diff) {
+                continue;
+            }
+            let dev = diff.deviation;
+            let latency = ts - res[inj].timestamp;
+            if res[inj].latency.is_none_or(|l| latency < l) {
+                res[inj].latency = Some(latency);
+                res[inj].deviation = dev.clone();
+            }
+            if evt1.id != evt2.id || dev == "control-dev" {
+                break;
+            }
+        }
+    }
+    res
 }
 
 /*
@@ -280,3 +499,159 @@ pub fn compare_mc(tr1: &SymbolicTrace, tr2: &SymbolicTrace) -> bool {
     _f.check()
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    fn call(name: &str, args: Vec<u128>) -> Event {
+        let data = EventType::Call {
+            name: String::from(name),
+            value: get_value(0),
+            args: args.into_iter().map(get_value).collect(),
+        };
+        Event::new(data, "0", 1, "main")
+    }
+
+    fn fields(diff: &EventDiff) -> Vec<MaskField> {
+        let mut res = diff.fields.clone().unwrap().into_iter().collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    #[test]
+    fn diff_reports_the_first_deviation_and_all_fields() {
+        let tol = Tolerance::new();
+        let opts = CompareOptions::default();
+        let a = get_object("a", 0x1000, 8);
+        let b = get_object("b", 0x2000, 8);
+        let diff = get_event_diff(
+            &get_write("0", 1, &a, 0, 1),
+            &get_write("0", 1, &b, 0, 2),
+            &tol,
+            &opts,
+        );
+        assert_eq!(diff.deviation, "data-dev");
+        assert_eq!(fields(&diff), vec![MaskField::Value, MaskField::Pointer]);
+        let diff = get_event_diff(
+            &get_read("0", 1, &a, 0, 1),
+            &get_read("0", 1, &a, 4, 1),
+            &tol,
+            &opts,
+        );
+        assert_eq!(diff.deviation, "addr-dev");
+        assert_eq!(fields(&diff), vec![MaskField::Pointer]);
+        let diff = get_event_diff(
+            &get_read("0", 1, &a, 0, 1),
+            &get_read("0", 1, &a, 0, 1),
+            &tol,
+            &opts,
+        );
+        assert_eq!(diff.deviation, "no-dev");
+        assert!(!diff.is_deviation());
+    }
+
+    #[test]
+    fn diff_of_calls_and_branches() {
+        let tol = Tolerance::new();
+        let opts = CompareOptions::default();
+        let diff = get_event_diff(&call("f", vec![1, 2]), &call("f", vec![1, 3]), &tol, &opts);
+        assert_eq!(diff.deviation, "data-dev");
+        assert_eq!(fields(&diff), vec![MaskField::Args]);
+        let diff = get_event_diff(&call("f", vec![1]), &call("f", vec![1, 3]), &tol, &opts);
+        assert_eq!(diff.deviation, "control-dev");
+        assert!(diff.fields.is_none());
+        let diff = get_event_diff(&call("f", vec![]), &call("g", vec![]), &tol, &opts);
+        assert_eq!(diff.deviation, "control-dev");
+        assert!(diff.fields.is_none());
+        let diff = get_event_diff(&get_branch("0", 1, 2), &get_branch("0", 1, 3), &tol, &opts);
+        assert_eq!(diff.deviation, "control-dev");
+        assert_eq!(fields(&diff), vec![MaskField::Branch]);
+    }
+
+    #[test]
+    fn call_context_is_only_compared_on_request() {
+        let a = get_object("a", 0x1000, 8);
+        let ev1 = get_write("0", 1, &a, 0, 1);
+        let mut ev2 = get_write("0", 1, &a, 0, 1);
+        ev2.depth = 1;
+        let tol = Tolerance::new();
+        let mut opts = CompareOptions::default();
+        assert_eq!(get_event_diff(&ev1, &ev2, &tol, &opts).deviation, "no-dev");
+        opts.call_context = true;
+        let diff = get_event_diff(&ev1, &ev2, &tol, &opts);
+        assert_eq!(diff.deviation, "control-dev");
+        assert!(diff.fields.is_none());
+        ev2.depth = 0;
+        ev2.func = String::from("f");
+        assert_eq!(
+            get_event_diff(&ev1, &ev2, &tol, &opts).deviation,
+            "control-dev"
+        );
+        ev2.func = ev1.func.clone();
+        assert_eq!(get_event_diff(&ev1, &ev2, &tol, &opts).deviation, "no-dev");
+    }
+
+    #[test]
+    fn comparison_suppresses_masked_deviations() {
+        let a = get_object("a", 0x1000, 8);
+        let golden = get_trace(
+            "gr",
+            vec![get_write("0", 1, &a, 0, 1), get_write("0", 2, &a, 0, 1)],
+        );
+        let faulty = get_trace(
+            "fi",
+            vec![get_write("0", 1, &a, 0, 2), get_write("0", 2, &a, 0, 1)],
+        );
+        let tol = Tolerance::new();
+        let opts = CompareOptions::default();
+        let res = compare_naive(&golden, &faulty, &tol, &opts, &Mask::new());
+        assert_eq!(res.deviation, "data-dev");
+        assert_eq!(res.id, "1");
+        let mut mask = Mask::new();
+        mask.insert("1", MaskField::Value);
+        let res = compare_naive(&golden, &faulty, &tol, &opts, &mask);
+        assert_eq!(res.deviation, "no-dev");
+        assert_eq!(res.suppressed, 1);
+    }
+
+    #[test]
+    fn deviations_are_attributed_to_the_preceding_injection() {
+        let a = get_object("a", 0x1000, 8);
+        let values = |dev1: u128, dev2: u128| {
+            vec![
+                get_write("0", 1, &a, 0, 1),
+                get_write("0", 2, &a, 0, dev1),
+                get_write("0", 3, &a, 0, 1),
+                get_write("0", 4, &a, 0, 1),
+                get_write("0", 5, &a, 0, dev2),
+            ]
+        };
+        let golden = get_trace("gr", values(1, 1));
+        let mut faulty = get_trace("fi", values(2, 2));
+        // the injections are not ordered in the _faultinj file
+        faulty.injection = vec![(3, 4), (0, 1), (10, 9)];
+        assert_eq!(faulty.get_first_injection(), Some(0));
+        assert_eq!(faulty.get_preceding_injection(0), None);
+        assert_eq!(faulty.get_preceding_injection(4), Some(0));
+        let tol = Tolerance::new();
+        let opts = CompareOptions::default();
+        let res = compare_naive(&golden, &faulty, &tol, &opts, &Mask::new());
+        assert_eq!((&*res.id, res.injection), ("2", Some(1)));
+        let reports = get_injection_reports(&golden, &faulty, &tol, &opts, &Mask::new());
+        let summary = reports
+            .iter()
+            .map(|r| (r.instruction, &*r.deviation, r.latency))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (4, "data-dev", Some(1)),
+                (1, "data-dev", Some(1)),
+                (9, "no-dev", None),
+            ]
+        );
+        assert!(!reports[2].is_activated());
+    }
+}
@@ -0,0 +1,246 @@
+use memcheck::*;
+use object::*;
+use outcome::*;
+use slice::*;
+use std::sync::Arc;
+use trace::*;
+use utils::*;
+
+// addresses below are considered as null pointer dereferences
+const NULL_PAGE: u64 = 4096;
+// number of events reported per thread before the termination
+const LAST_EVENTS: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessTarget {
+    InBounds { object: Arc<Object>, offset: u64 },
+    Null,
+    // the address isn't inside any live object, see memcheck
+    Invalid(Violation),
+}
+
+// A load or store of the raw trace, also if the address couldn't be
+// resolved to an object and no event has been created for it.
+#[derive(Clone, Debug)]
+pub struct MemoryAccess {
+    pub timestamp: u64,
+    // position in the raw trace
+    pub pos: usize,
+    pub id: u32,
+    pub func: String,
+    pub is_write: bool,
+    pub address: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct CrashReport {
+    pub signal: i64,
+    pub thread: String,
+    pub access: Option<(MemoryAccess, AccessTarget)>,
+    pub last_events: Vec<(String, Vec<String>)>,
+}
+
+impl AccessTarget {
+    pub fn resolve(tr: &SymbolicTrace, access: &MemoryAccess) -> AccessTarget {
+        if access.address < NULL_PAGE {
+            return AccessTarget::Null;
+        }
+        let index = ObjectIndex::new(&tr.objects);
+        match index.get_live_object(access.address, access.pos) {
+            Some(obj) => AccessTarget::InBounds {
+                offset: access.address - obj.address,
+                object: obj,
+            },
+            None => AccessTarget::Invalid(index.check_address(access.address, access.pos)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessTarget::InBounds { .. } => "in-bounds",
+            AccessTarget::Null => "null",
+            AccessTarget::Invalid(v) => v.name(),
+        }
+    }
+
+    // Object and offset of the access, the offset is negative before the
+    // start of the object.
+    fn get_object(&self) -> (String, String) {
+        match self {
+            AccessTarget::InBounds { object, offset }
+            | AccessTarget::Invalid(Violation::UseAfterFree { object, offset }) => {
+                (object.id.clone(), format!("{}", offset))
+            }
+            AccessTarget::Invalid(Violation::OutOfBounds { object, distance }) => {
+                let offset = if *distance < 0 {
+                    *distance
+                } else {
+                    object.size as i64 + distance
+                };
+                (object.id.clone(), format!("{}", offset))
+            }
+            _ => (String::new(), String::new()),
+        }
+    }
+}
+
+// Crash site of a run terminated by a signal: the last memory access of the
+// trace and the last events of every thread.
+pub fn get_crash_report(tr: &SymbolicTrace) -> Option<CrashReport> {
+    let signal = match get_exit_status(&tr.ret_code) {
+        ExitStatus::Signal(s) => s,
+        _ => return None,
+    };
+    // only the final access is resolved, the others aren't reported
+    let access = tr
+        .last_accesses
+        .values()
+        .max_by_key(|a| a.timestamp)
+        .map(|a| (a.clone(), AccessTarget::resolve(tr, a)));
+    let thread = tr
+        .last_accesses
+        .iter()
+        .max_by_key(|(_, a)| a.timestamp)
+        .map(|(th, _)| th.clone())
+        .or_else(|| tr.events.last().map(|ev| ev.thread.clone()))
+        .unwrap_or_default();
+    let mut threads = tr.thread_naming.values().collect::<Vec<_>>();
+    threads.sort();
+    let last_events = threads
+        .into_iter()
+        .map(|th| {
+            let mut evs = tr
+                .events
+                .iter()
+                .rev()
+                .filter(|ev| ev.thread == *th)
+                .take(LAST_EVENTS)
+                .map(|ev| format!("ID {} {}", ev.id, get_event_label(ev)))
+                .collect::<Vec<_>>();
+            evs.reverse();
+            (th.clone(), evs)
+        })
+        .collect();
+    Some(CrashReport {
+        signal,
+        thread,
+        access,
+        last_events,
+    })
+}
+
+pub fn get_crash_header() -> Vec<&'static str> {
+    vec![
+        "Benchmark",
+        "Fault-model",
+        "#",
+        "Signal",
+        "Thread",
+        "ID",
+        "Function",
+        "Location",
+        "Access",
+        "Address",
+        "Object",
+        "Offset",
+        "Classification",
+        "Last-events",
+    ]
+}
+
+pub fn get_crash_record(tr: &SymbolicTrace, crash: &CrashReport) -> Vec<String> {
+    let inf = tr.get_id_info();
+    let mut res = vec![
+        inf.0,
+        inf.1,
+        inf.2,
+        format!("{}", crash.signal),
+        crash.thread.clone(),
+    ];
+    match &crash.access {
+        Some((a, target)) => {
+            let (obj, off) = target.get_object();
+            res.extend(vec![
+                format!("{}", a.id),
+                a.func.clone(),
+                tr.get_location(&format!("{}", a.id)),
+                String::from(if a.is_write { "write" } else { "read" }),
+                to_hex(a.address),
+                obj,
+                off,
+                String::from(target.name()),
+            ]);
+        }
+        None => res.extend(vec![String::new(); 8]),
+    }
+    res.push(
+        crash
+            .last_events
+            .iter()
+            .map(|(th, evs)| format!("{}: {}", th, evs.join("; ")))
+            .collect::<Vec<_>>()
+            .join(" | "),
+    );
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    fn get_access(pos: usize, address: u64) -> MemoryAccess {
+        MemoryAccess {
+            timestamp: pos as u64,
+            pos,
+            id: 7,
+            func: String::from("main"),
+            is_write: true,
+            address,
+        }
+    }
+
+    fn get_crashed_trace() -> SymbolicTrace {
+        let mut a = (*get_object("a", 0x1000, 16)).clone();
+        a.destruction = 10;
+        let mut b = (*get_object("b", 0x2000, 8)).clone();
+        b.destruction = 2;
+        let a = Arc::new(a);
+        let mut tr = get_trace("bench_trace.1-0", vec![get_write("0", 1, &a, 0, 1)]);
+        tr.objects = vec![Arc::new(Object::get_null()), a, Arc::new(b)];
+        tr.ret_code = String::from("139");
+        tr
+    }
+
+    #[test]
+    fn accesses_are_resolved_with_memcheck() {
+        let tr = get_crashed_trace();
+        let resolve = |address| AccessTarget::resolve(&tr, &get_access(5, address));
+        assert_eq!(resolve(0x1004).name(), "in-bounds");
+        assert_eq!(resolve(0x8), AccessTarget::Null);
+        assert_eq!(resolve(0x1010).name(), "out-of-bounds");
+        assert_eq!(
+            resolve(0x2004).get_object(),
+            (String::from("b"), String::from("4"))
+        );
+        assert_eq!(resolve(0x2004).name(), "use-after-free");
+        assert_eq!(resolve(0x900000).name(), "wild");
+    }
+
+    #[test]
+    fn crash_is_located_at_the_last_access() {
+        let mut tr = get_crashed_trace();
+        tr.last_accesses
+            .insert(String::from("0"), get_access(5, 0x1010));
+        tr.last_accesses
+            .insert(String::from("1"), get_access(3, 0x8));
+        let crash = get_crash_report(&tr).unwrap();
+        assert_eq!((crash.signal, &*crash.thread), (11, "0"));
+        let rec = get_crash_record(&tr, &crash);
+        assert_eq!(rec.len(), get_crash_header().len());
+        assert_eq!(rec[8..13], ["write", "1010", "a", "16", "out-of-bounds"]);
+
+        tr.ret_code = String::from("0");
+        assert!(get_crash_report(&tr).is_none());
+    }
+}
@@ -2,8 +2,16 @@
 use csv::*;
 use error::*;
 use instruction::*;
+use layout::*;
+use memmap::*;
 use log::*;
+use mask::*;
 use object::*;
+use rules::*;
+use slice::*;
+use source::*;
+use summary::*;
+use symbols::*;
 use petgraph::dot::{Config, Dot};
 use petgraph::graphmap::DiGraphMap;
 use petgraph::visit::Bfs;
@@ -18,21 +26,45 @@ use std::path::Path;
 use std::process;
 use std::result::Result;
 use std::sync::{Arc, Mutex};
+use tolerance::*;
 use trace::*;
 use utils::*;
 
-pub fn read_instructions(f_name: &str) -> Result<Vec<Instruction>, Box<Error>> {
+// Returns the instructions and whether the trace is truncated, i.e. its last
+// record can't be read or is incomplete because the run has been killed while
+// writing it.
+pub fn read_instructions(f_name: &str) -> Result<(Vec<Instruction>, bool), Box<Error>> {
     info!("Loading instructions...");
-    let mut res = Vec::new();
     let file = File::open(&*f_name)?;
-    let mut rdr = Reader::from_reader(file);
+    Ok(parse_instructions(file, f_name))
+}
+
+pub fn parse_instructions<R: Read>(reader: R, f_name: &str) -> (Vec<Instruction>, bool) {
+    let mut res = Vec::new();
+    let mut is_rejected = false;
+    let mut rdr = ReaderBuilder::new().flexible(true).from_reader(reader);
     for result in rdr.records() {
-        let record = result?;
-        if let Some(inst) = Instruction::from(&record) {
-            res.push(inst);
+        let record = match result {
+            Ok(r) => r,
+            Err(e) => {
+                warn!("Trace {} ends with an unreadable record: {}", f_name, e);
+                return (res, true);
+            }
+        };
+        // records of filtered instructions are skipped, they only mean that
+        // the trace is incomplete if they are the last one
+        match Instruction::from(&record) {
+            Some(inst) => {
+                res.push(inst);
+                is_rejected = false;
+            }
+            None => is_rejected = true,
         }
     }
-    Ok(res)
+    if is_rejected {
+        warn!("Trace {} ends with an incomplete record", f_name);
+    }
+    (res, is_rejected)
 }
 
 pub fn read_output_hash(fname: &str) -> Result<String, Box<Error>> {
@@ -76,6 +108,112 @@ pub fn read_injections(f_name: &str) -> Result<Vec<(u64, u32)>, Box<Error>> {
     Ok(res)
 }
 
+pub fn read_tolerance(f_name: &str) -> Result<Tolerance, Box<Error>> {
+    info!("Loading tolerance rules...");
+    let mut res = Tolerance::new();
+    let file = File::open(&*f_name)?;
+    let mut rdr = Reader::from_reader(file);
+    for result in rdr.records() {
+        let record = result?;
+        let scope = record.get(0).ok_or("tolerance scope missing")?.trim();
+        let target = record.get(1).ok_or("tolerance target missing")?.trim();
+        let kind = record.get(2).ok_or("tolerance kind missing")?.trim();
+        let value = record.get(3).ok_or("tolerance value missing")?.trim();
+        res.add_rule(scope, target, ToleranceRule::from(kind, value)?)?;
+    }
+    Ok(res)
+}
+
+pub fn read_rules(f_name: &str) -> Result<Rules, Box<Error>> {
+    info!("Loading rules...");
+    let content = read_to_string(f_name)?;
+    let rules: Rules = toml::from_str(&*content)?;
+    Ok(rules)
+}
+
+pub fn read_layout(f_name: &str) -> Result<Layout, Box<Error>> {
+    info!("Loading type layouts...");
+    let content = read_to_string(f_name)?;
+    let layout: Layout = toml::from_str(&*content)?;
+    Ok(layout)
+}
+
+// Mapping file with the columns ID, File, Line and Function.
+pub fn read_source_map(f_name: &str) -> Result<SourceMap, Box<Error>> {
+    info!("Loading source locations...");
+    let mut res = SourceMap::new();
+    let file = File::open(&*f_name)?;
+    let mut rdr = Reader::from_reader(file);
+    for result in rdr.records() {
+        let record = result?;
+        if let (Some(id), Some(file), Some(line)) = (record.get(0), record.get(1), record.get(2)) {
+            res.insert(
+                id.trim().parse()?,
+                SourceLocation {
+                    file: String::from(file),
+                    line: line.trim().parse()?,
+                    function: String::from(record.get(3).unwrap_or("")),
+                },
+            );
+        }
+    }
+    Ok(res)
+}
+
+// Mapping file with the columns Symbol, Name and (optionally) Size and TLS.
+pub fn read_symbol_map(f_name: &str) -> Result<SymbolMap, Box<Error>> {
+    info!("Loading symbols...");
+    let mut res = SymbolMap::new();
+    let file = File::open(&*f_name)?;
+    let mut rdr = ReaderBuilder::new().flexible(true).from_reader(file);
+    for result in rdr.records() {
+        let record = result?;
+        if let (Some(symbol), Some(name)) = (record.get(0), record.get(1)) {
+            let size = match record.get(2).map(|s| s.trim()) {
+                Some(s) if !s.is_empty() => Some(s.parse()?),
+                _ => None,
+            };
+            let tls = record
+                .get(3)
+                .is_some_and(|s| ["1", "true", "tls"].contains(&&*s.trim().to_lowercase()));
+            res.insert(
+                symbol,
+                Symbol {
+                    name: String::from(name),
+                    size,
+                    tls,
+                },
+            );
+        }
+    }
+    Ok(res)
+}
+
+pub fn read_mask(f_name: &str) -> Result<Mask, Box<Error>> {
+    info!("Loading nondeterminism mask...");
+    let mut res = Mask::new();
+    let file = File::open(&*f_name)?;
+    let mut rdr = Reader::from_reader(file);
+    for result in rdr.records() {
+        let record = result?;
+        let id = record.get(0).ok_or("mask id missing")?;
+        let field = record.get(1).ok_or("mask field missing")?;
+        res.insert(id, MaskField::from(field)?);
+    }
+    Ok(res)
+}
+
+pub fn write_mask(f_name: &str, mask: &Mask) -> Result<(), Box<Error>> {
+    warn!("Writing {} mask entries to {}", mask.len(), f_name);
+    let file = File::create(f_name)?;
+    let mut wtr = Writer::from_writer(file);
+    wtr.write_record(&["ID", "Field"])?;
+    for (id, field) in mask.get_entries() {
+        wtr.write_record(&[&*id, field.name()])?;
+    }
+    Ok(())
+}
+
 pub fn write_check_results(fname: &str, res: Vec<String>) -> Result<(), Box<Error>>{
     //let file = Some(File::create(fname)?);
     let file = OpenOptions::new()
@@ -89,11 +227,49 @@ pub fn write_check_results(fname: &str, res: Vec<String>) -> Result<(), Box<Erro
     wtr.write_record(&[&*res[0], &*res[1], &*res[2], &*res[3], &*res[4], &*res[5]])?;
     Ok(())
 }
+pub const RESULT_COLUMNS: &[&str] = &[
+    "Benchmark",
+    "Fault-model",
+    "#",
+    "Deviation",
+    "Output-Deviation",
+    "Exit-code",
+    "Fault-active",
+    "Symbolification",
+    "Comparison",
+    "Tolerated",
+    "Suppressed",
+    "Outcome",
+    "Injection",
+    "Deviation-ID",
+    "Deviation-field",
+    "Deviation-location",
+    "Latency-events",
+    "Latency-thread-events",
+    "Latency-time",
+    "Latency-manifestation",
+    "Truncated",
+];
+
+pub fn get_column(name: &str) -> usize {
+    RESULT_COLUMNS.iter().position(|x| *x == name).unwrap()
+}
+
 pub fn write_results(
     fname: &str,
     results: Vec<Vec<String>>,
     is_override: bool,
     is_append: bool,
+) -> Result<(), Box<Error>> {
+    write_table(fname, RESULT_COLUMNS, results, is_override, is_append)
+}
+
+pub fn write_table(
+    fname: &str,
+    header: &[&str],
+    results: Vec<Vec<String>>,
+    is_override: bool,
+    is_append: bool,
 ) -> Result<(), Box<Error>> {
     let mut tmp_name = String::from(fname);
     let mut i = 0;
@@ -116,26 +292,57 @@ pub fn write_results(
     }
     let mut wtr = Writer::from_writer(file.unwrap());
     if !is_append {
-        wtr.write_record(&[
-            "Benchmark",
-            "Fault-model",
-            "#",
-            "Deviation",
-            "Output-Deviation",
-            "Exit-code",
-            "Fault-active",
-            "Symbolification",
-            "Comparison",
-        ])?;
+        wtr.write_record(header)?;
     }
     for rec in results.iter() {
-        wtr.write_record(&[
-            &*rec[0], &*rec[1], &*rec[2], &*rec[3], &*rec[4], &*rec[5], &*rec[6], &*rec[7], &*rec[8]
-        ])?;
+        wtr.write_record(rec)?;
     }
     Ok(())
 }
 
+// Reads a results table, the columns are rearranged to RESULT_COLUMNS so
+// that tables written by older versions can be read as well.
+pub fn read_results(fname: &str) -> Result<Vec<Vec<String>>, Box<Error>> {
+    info!("Loading results...");
+    let file = File::open(fname)?;
+    let mut rdr = Reader::from_reader(file);
+    let header = rdr.headers()?.clone();
+    let pos = RESULT_COLUMNS
+        .iter()
+        .map(|c| header.iter().position(|h| h == *c))
+        .collect::<Vec<_>>();
+    let mut res = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let rec = pos
+            .iter()
+            .map(|p| String::from(p.and_then(|i| record.get(i)).unwrap_or("")))
+            .collect::<Vec<_>>();
+        res.push(rec);
+    }
+    Ok(res)
+}
+
+pub fn write_summary(fname: &str, summary: &[Summary]) -> Result<(), Box<Error>> {
+    let records = summary.iter().map(|s| s.get_record()).collect::<Vec<_>>();
+    let header = get_summary_header();
+    let header = header.iter().map(|x| &**x).collect::<Vec<_>>();
+    write_table(fname, &*header, records, true, false)?;
+    let json_name = format!("{}.json", fname);
+    warn!("Writing {} summary items to {}", summary.len(), json_name);
+    write(json_name, serde_json::to_string_pretty(summary)?)?;
+    Ok(())
+}
+
+pub fn write_memmap(fname: &str, entries: &[MemoryEntry], is_overwrite: bool) -> Result<(), Box<Error>> {
+    let records = entries.iter().map(|e| e.get_record()).collect::<Vec<_>>();
+    write_table(fname, &get_memmap_header(), records, is_overwrite, false)?;
+    let json_name = format!("{}.json", fname);
+    warn!("Writing {} memory map items to {}", entries.len(), json_name);
+    write(json_name, serde_json::to_string_pretty(entries)?)?;
+    Ok(())
+}
+
 pub fn read_logical_mapping(f_name: &str) -> Result<HashMap<u64, String>, Box<Error>> {
     info!("loading logical naming...");
     let mut res = HashMap::new();
@@ -169,6 +376,12 @@ pub fn write_dot(graph: &DiGraphMap<&Event, &str>, f_name: &str) -> Result<(), B
     Ok(())
 }
 
+pub fn write_slice(f_name: &str, tr: &SymbolicTrace, slice: &Slice) -> Result<(), Box<Error>> {
+    write_table(f_name, &*get_slice_header(), slice.get_records(tr), true, false)?;
+    write_to_file(&*slice.get_dot(tr), &*format!("{}.dot", f_name))?;
+    Ok(())
+}
+
 pub fn read_thread_graph(
     m_name: &str,
     root: u64,
@@ -276,17 +489,55 @@ pub fn read_globals(
     g_name: &str,
     dest: usize,
     objects: &mut Vec<Arc<Object>>,
+    symbols: &SymbolMap,
 ) -> Result<(), Box<Error>> {
     info!("Loading globals...");
     objects.push(Arc::new(Object::get_null()));
     let _file = File::open(g_name)?;
     let mut _rdr = Reader::from_reader(_file);
+    let mut globals = Vec::new();
     for result in _rdr.records() {
         let _record = result?;
         let mut obj = Object::from_global(&_record);
         obj.update_validity(dest);
+        globals.push(obj);
+    }
+    for obj in name_globals(globals, symbols) {
         objects.push(Arc::new(obj));
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACE: &str = "Timestamp,Thread,ID,Op,Value,Operands
+1,1,7,call-malloc-u,14-8-0000000000001000,10-8-0000000000000010
+2,1,8,store,10-4-0000000a,10-4-0000000a,14-8-0000000000001000
+";
+
+    #[test]
+    fn complete_traces_are_not_truncated() {
+        let (insts, truncated) = parse_instructions(TRACE.as_bytes(), "trace");
+        assert_eq!(insts.len(), 2);
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn traces_with_a_short_last_record_are_truncated() {
+        let trace = format!("{}2,1,8,lo", TRACE);
+        let (insts, truncated) = parse_instructions(trace.as_bytes(), "trace");
+        assert_eq!(insts.len(), 2);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn traces_with_a_cut_off_last_value_are_truncated() {
+        let trace = format!("{}3,1,9,load,10-4-0000000a,14-8-00000", TRACE);
+        let (insts, truncated) = parse_instructions(trace.as_bytes(), "trace");
+        assert_eq!(insts.len(), 2);
+        assert!(truncated);
+    }
+}
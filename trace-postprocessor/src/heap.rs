@@ -0,0 +1,294 @@
+use instruction::*;
+use log::*;
+use object::*;
+use std::collections::HashMap;
+use trace::*;
+use utils::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeapErrorKind {
+    DoubleFree,
+    // free of a pointer that has never been returned by an allocation, or
+    // that points into the middle of a block
+    InvalidFree,
+}
+
+#[derive(Clone, Debug)]
+pub struct HeapError {
+    pub kind: HeapErrorKind,
+    pub timestamp: u64,
+    pub id: u32,
+    pub thread: String,
+    pub address: u64,
+    // the block the pointer has been attributed to
+    pub object: Option<String>,
+}
+
+// A heap block with its lifetime, `freed` is None as long as it is live.
+#[derive(Clone, Debug)]
+pub struct HeapBlock {
+    pub object: Object,
+    pub freed: Option<usize>,
+}
+
+// Heap of a trace. The blocks are named by their owning thread, allocation
+// site and the order of the allocations at this site (see ObjectNaming),
+// never by their concrete address, so that a different allocator or ASLR
+// doesn't change the names. Frees are matched against the live blocks only.
+#[derive(Clone, Debug, Default)]
+pub struct HeapModel {
+    naming: ObjectNaming,
+    blocks: Vec<HeapBlock>,
+    live: HashMap<u64, usize>,
+    pub errors: Vec<HeapError>,
+}
+
+impl HeapErrorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HeapErrorKind::DoubleFree => "double-free",
+            HeapErrorKind::InvalidFree => "invalid-free",
+        }
+    }
+}
+
+impl HeapModel {
+    pub fn new() -> HeapModel {
+        HeapModel::default()
+    }
+
+    pub fn allocate(&mut self, inst: &Instruction, pos: usize, owner: &str, frames: &[String]) {
+        if get_hex(&inst.value.value) == 0 {
+            // failed allocation
+            return;
+        }
+        let name = self.naming.get_name(owner, frames, inst.instruction_id);
+        let obj = Object::from(inst, &name, pos, true, owner).remove(0);
+        if let Some(i) = self.live.remove(&obj.address) {
+            // the free of the previous block has been missed
+            warn!("Heap address {} reused without free", to_hex(obj.address));
+            self.blocks[i].freed = Some(pos);
+        }
+        self.live.insert(obj.address, self.blocks.len());
+        self.blocks.push(HeapBlock {
+            object: obj,
+            freed: None,
+        });
+    }
+
+    // Frees the block at the first operand of a free, delete or realloc and
+    // returns it with its final lifetime. The new block of a realloc is
+    // allocated separately, after the old one has been freed.
+    pub fn free(&mut self, inst: &Instruction, pos: usize, owner: &str) -> Option<Object> {
+        let address = get_hex(&inst.operands[0].value);
+        if address == 0 {
+            return None;
+        }
+        if inst.is_realloc()
+            && get_hex(&inst.value.value) == 0
+            && get_hex(&inst.operands[1].value) != 0
+        {
+            // a failed realloc leaves the old block untouched
+            return None;
+        }
+        if let Some(i) = self.live.remove(&address) {
+            let block = &mut self.blocks[i];
+            block.freed = Some(pos);
+            let mut obj = block.object.clone();
+            obj.update_validity(pos);
+            return Some(obj);
+        }
+        let (kind, object) = match self.get_block(address, pos) {
+            Some(b) => (HeapErrorKind::InvalidFree, Some(b.object.id.clone())),
+            None => match self
+                .blocks
+                .iter()
+                .rev()
+                .find(|b| b.freed.is_some() && b.object.address == address)
+            {
+                Some(b) => (HeapErrorKind::DoubleFree, Some(b.object.id.clone())),
+                None => (HeapErrorKind::InvalidFree, None),
+            },
+        };
+        warn!("{} of {}", kind.name(), to_hex(address));
+        self.errors.push(HeapError {
+            kind,
+            timestamp: inst.timestamp,
+            id: inst.instruction_id,
+            thread: String::from(owner),
+            address,
+            object,
+        });
+        None
+    }
+
+    // Remaining live blocks, they are considered to be valid until `pos`.
+    pub fn drain(&mut self, pos: usize) -> Vec<Object> {
+        let mut idxs = self.live.drain().map(|(_, i)| i).collect::<Vec<_>>();
+        idxs.sort();
+        idxs.into_iter()
+            .map(|i| {
+                let mut obj = self.blocks[i].object.clone();
+                obj.update_validity(pos);
+                obj
+            })
+            .collect()
+    }
+
+    pub fn get_blocks(&self) -> &[HeapBlock] {
+        &self.blocks
+    }
+
+    // Block that contains the address at the trace position `pos`.
+    pub fn get_block(&self, address: u64, pos: usize) -> Option<&HeapBlock> {
+        self.blocks.iter().rev().find(|b| {
+            address >= b.object.address
+                && address < b.object.address + b.object.size
+                && pos >= b.object.construction
+                && b.freed.is_none_or(|f| pos <= f)
+        })
+    }
+}
+
+pub fn get_heap_header() -> Vec<&'static str> {
+    vec![
+        "Benchmark",
+        "Fault-model",
+        "#",
+        "Timestamp",
+        "Thread",
+        "ID",
+        "Location",
+        "Error",
+        "Address",
+        "Object",
+    ]
+}
+
+pub fn get_heap_records(tr: &SymbolicTrace) -> Vec<Vec<String>> {
+    let inf = tr.get_id_info();
+    tr.heap
+        .errors
+        .iter()
+        .map(|e| {
+            vec![
+                inf.0.clone(),
+                inf.1.clone(),
+                inf.2.clone(),
+                format!("{}", e.timestamp),
+                e.thread.clone(),
+                format!("{}", e.id),
+                tr.get_location(&format!("{}", e.id)),
+                String::from(e.kind.name()),
+                to_hex(e.address),
+                e.object.clone().unwrap_or_default(),
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    fn malloc(heap: &mut HeapModel, pos: usize, address: u64, size: u64) {
+        let inst = get_instruction(&format!(
+            "{},1,7,call-malloc-u,14-8-{:016x},10-8-{:016x}",
+            pos, address, size
+        ));
+        heap.allocate(&inst, pos, "0", &[]);
+    }
+
+    fn free(heap: &mut HeapModel, pos: usize, address: u64) -> Option<Object> {
+        let inst = get_instruction(&format!(
+            "{},1,8,call-free-u,10-4-00000000,14-8-{:016x}",
+            pos, address
+        ));
+        heap.free(&inst, pos, "0")
+    }
+
+    // realloc is a free of the old block followed by an allocation
+    fn realloc(heap: &mut HeapModel, pos: usize, old: u64, new: u64, size: u64) -> Option<Object> {
+        let inst = get_instruction(&format!(
+            "{},1,9,call-realloc-u,14-8-{:016x},14-8-{:016x},10-8-{:016x}",
+            pos, new, old, size
+        ));
+        let res = heap.free(&inst, pos, "0");
+        heap.allocate(&inst, pos, "0", &[]);
+        res
+    }
+
+    fn get_errors(heap: &HeapModel) -> Vec<(&'static str, Option<&str>)> {
+        heap.errors
+            .iter()
+            .map(|e| (e.kind.name(), e.object.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn blocks_are_named_after_their_allocation_site() {
+        let mut heap = HeapModel::new();
+        malloc(&mut heap, 1, 0x1000, 16);
+        malloc(&mut heap, 2, 0x2000, 8);
+        let freed = free(&mut heap, 3, 0x1000).unwrap();
+        assert_eq!(
+            (&*freed.id, freed.construction, freed.destruction),
+            ("0::7#1", 1, 3)
+        );
+        assert_eq!(freed.size, 16);
+        assert!(heap.get_block(0x1008, 2).is_some());
+        assert!(heap.get_block(0x1008, 4).is_none());
+        assert_eq!(heap.get_block(0x2004, 4).unwrap().object.id, "0::7#2");
+        let remaining = heap.drain(10);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(
+            (&*remaining[0].id, remaining[0].destruction),
+            ("0::7#2", 10)
+        );
+        assert!(heap.errors.is_empty());
+    }
+
+    #[test]
+    fn invalid_and_double_frees_are_reported() {
+        let mut heap = HeapModel::new();
+        malloc(&mut heap, 1, 0x1000, 16);
+        assert!(free(&mut heap, 2, 0x1008).is_none());
+        assert!(free(&mut heap, 3, 0x1000).is_some());
+        assert!(free(&mut heap, 4, 0x1000).is_none());
+        assert!(free(&mut heap, 5, 0x5000).is_none());
+        assert!(free(&mut heap, 6, 0).is_none());
+        assert_eq!(
+            get_errors(&heap),
+            vec![
+                ("invalid-free", Some("0::7#1")),
+                ("double-free", Some("0::7#1")),
+                ("invalid-free", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn realloc_frees_the_old_block() {
+        let mut heap = HeapModel::new();
+        malloc(&mut heap, 1, 0x1000, 16);
+        // moved to a new block
+        let old = realloc(&mut heap, 2, 0x1000, 0x2000, 32).unwrap();
+        assert_eq!((&*old.id, old.destruction), ("0::7#1", 2));
+        let block = heap.get_block(0x2010, 3).unwrap();
+        assert_eq!((&*block.object.id, block.object.size), ("0::9#1", 32));
+        // grown in place
+        let old = realloc(&mut heap, 3, 0x2000, 0x2000, 64).unwrap();
+        assert_eq!(&*old.id, "0::9#1");
+        assert_eq!(heap.get_block(0x2030, 4).unwrap().object.id, "0::9#2");
+        // a failed realloc keeps the old block
+        assert!(realloc(&mut heap, 4, 0x2000, 0, 128).is_none());
+        // realloc of NULL is a malloc
+        assert!(realloc(&mut heap, 5, 0, 0x3000, 8).is_none());
+        assert!(heap.errors.is_empty());
+        let remaining = heap.drain(10);
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(remaining[0].id, "0::9#2");
+        assert_eq!(remaining[1].id, "0::9#3");
+    }
+}
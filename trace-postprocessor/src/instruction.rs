@@ -1,5 +1,6 @@
 use csv::*;
 use log::*;
+use value::*;
 #[derive(Debug)]
 pub struct Value {
     pub value: String,
@@ -31,58 +32,61 @@ impl Value {
         if v[0].len() < 2 {
             return None;
         }
-        let size: u64 = v[1].to_string().parse().unwrap();
+        let size: u64 = v[1].parse().ok()?;
         if v[2].len() as u64 != size * 2 {
             return None;
         }
 
         Some(Value {
             value: v[2].to_string(),
-            type_id: v[0].to_string().parse().unwrap(),
-            type_size: v[1].to_string().parse().unwrap(),
+            type_id: v[0].parse().ok()?,
+            type_size: size,
         })
     }
 
     pub fn is_pointer(&self) -> bool {
-        return self.type_id == 14;
+        return self.type_id == POINTER_TYPE;
+    }
+
+    pub fn decode(&self) -> TypedValue {
+        TypedValue::from(self)
     }
 }
 
 impl Instruction {
     pub fn from(record: &StringRecord) -> Option<Instruction> {
+        // a record cut off by the end of the trace lacks fields
         for i in 0..5 {
-            let st = record.get(i).unwrap();
-            if st.is_empty() {
+            if record.get(i)?.is_empty() {
                 return None;
             }
         }
 
-        if let None = Value::from(record.get(4).unwrap()) {
-            return None;
-        }
-
-        if record.get(0).unwrap().to_string().len() > 16 {
+        if record.get(0)?.len() > 16 {
             return None;
         }
 
         let mut inst = Instruction {
-            timestamp: record.get(0).unwrap().to_string().parse().unwrap(),
-            thread_id: record.get(1).unwrap().to_string().parse().unwrap(),
-            instruction_id: record.get(2).unwrap().to_string().parse().unwrap(),
-            op_name: String::from(record.get(3).unwrap()),
-            value: Value::from(record.get(4).unwrap()).unwrap(),
+            timestamp: record.get(0)?.parse().ok()?,
+            thread_id: record.get(1)?.parse().ok()?,
+            instruction_id: record.get(2)?.parse().ok()?,
+            op_name: String::from(record.get(3)?),
+            value: Value::from(record.get(4)?)?,
             operands: Vec::new(),
         };
-        for i in 5..record.len() {
-            let st = record.get(i).unwrap();
+        for st in record.iter().skip(5) {
             if let Some(v) = Value::from(st) {
                 inst.operands.push(v);
             }
         }
-        if (inst.is_store() || inst.is_alloca() || inst.is_join()) && inst.operands.len() < 2 {
+        if (inst.is_store() || inst.is_alloca() || inst.is_join() || inst.is_realloc())
+            && inst.operands.len() < 2
+        {
             return None;
         }
-        if (inst.is_load() || inst.is_malloc() || inst.is_free()) && inst.operands.len() < 1 {
+        if (inst.is_load() || inst.is_heap_allocation() || inst.is_deallocation())
+            && inst.operands.len() < 1
+        {
             return None;
         }
         if inst.is_branch() && (inst.operands.len() != 1 && inst.operands.len() != 3) {
@@ -142,7 +146,11 @@ impl Instruction {
         return self.is_call() && self.op_name.ends_with("-u");
     }
     pub fn is_allocation(&self) -> bool {
-        return self.is_alloca() || self.is_malloc() || self.is_znam();
+        return self.is_alloca() || self.is_heap_allocation();
+    }
+
+    pub fn is_heap_allocation(&self) -> bool {
+        return self.is_malloc() || self.is_new() || self.is_realloc();
     }
 
     pub fn is_call(&self) -> bool {
@@ -164,15 +172,41 @@ impl Instruction {
             || return self.op_name.starts_with("call-calloc");
     }
 
+    // operator new[] including the nothrow and aligned variants (_Znaj on 32 bit)
     pub fn is_znam(&self) -> bool {
-        return self.op_name.starts_with("call-_Znam");
+        return self.op_name.starts_with("call-_Znam") || self.op_name.starts_with("call-_Znaj");
     }
 
-    pub fn is_deallocation(&self) -> bool {
-        return self.is_free() || self.is_znam();
-    }
+    // operator new including the nothrow and aligned variants (_Znwj on 32 bit)
     pub fn is_znwm(&self) -> bool {
-        return self.op_name.starts_with("call-_Znwm");
+        return self.op_name.starts_with("call-_Znwm") || self.op_name.starts_with("call-_Znwj");
+    }
+
+    pub fn is_new(&self) -> bool {
+        return self.is_znwm() || self.is_znam();
+    }
+
+    // operator delete including the sized, nothrow and aligned variants
+    pub fn is_zdlpv(&self) -> bool {
+        return self.op_name.starts_with("call-_ZdlPv");
+    }
+
+    // operator delete[] including the sized, nothrow and aligned variants
+    pub fn is_zdapv(&self) -> bool {
+        return self.op_name.starts_with("call-_ZdaPv");
+    }
+
+    pub fn is_delete(&self) -> bool {
+        return self.is_zdlpv() || self.is_zdapv();
+    }
+
+    // realloc frees the block of its first operand and allocates a new one
+    pub fn is_realloc(&self) -> bool {
+        return self.op_name.starts_with("call-realloc");
+    }
+
+    pub fn is_deallocation(&self) -> bool {
+        return self.is_free() || self.is_delete() || self.is_realloc();
     }
     pub fn is_free(&self) -> bool {
         return self.op_name.starts_with("call-free");
@@ -189,3 +223,70 @@ impl Instruction {
         return self.op_name.starts_with("store");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use testutils::*;
+
+    #[test]
+    fn new_variants_are_heap_allocations() {
+        for op in [
+            "call-_Znwm-u",
+            "call-_Znam-u",
+            "call-_Znwj-u",
+            "call-_ZnwmRKSt9nothrow_t-u",
+            "call-_ZnamSt11align_val_t-u",
+        ] {
+            let inst = get_instruction(&format!(
+                "1,1,7,{},14-8-0000000000001000,10-8-0000000000000010",
+                op
+            ));
+            assert!(inst.is_heap_allocation(), "{}", op);
+            assert!(inst.is_allocation(), "{}", op);
+            assert!(!inst.is_deallocation(), "{}", op);
+        }
+    }
+
+    #[test]
+    fn delete_variants_are_deallocations() {
+        for op in [
+            "call-_ZdlPv-u",
+            "call-_ZdaPv-u",
+            "call-_ZdlPvm-u",
+            "call-_ZdaPvSt11align_val_t-u",
+            "call-free-u",
+        ] {
+            let inst =
+                get_instruction(&format!("1,1,7,{},10-4-00000000,14-8-0000000000001000", op));
+            assert!(inst.is_deallocation(), "{}", op);
+            assert!(!inst.is_heap_allocation(), "{}", op);
+        }
+    }
+
+    #[test]
+    fn realloc_is_a_deallocation_and_an_allocation() {
+        let inst = get_instruction(
+            "1,1,7,call-realloc-u,14-8-0000000000002000,14-8-0000000000001000,10-8-0000000000000020",
+        );
+        assert!(inst.is_heap_allocation());
+        assert!(inst.is_deallocation());
+        assert!(try_instruction(
+            "1,1,7,call-realloc-u,14-8-0000000000002000,14-8-0000000000001000"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn allocation_without_size_is_rejected() {
+        assert!(try_instruction("1,1,7,call-_Znwm-u,14-8-0000000000001000").is_none());
+    }
+
+    #[test]
+    fn cut_off_records_are_rejected() {
+        assert!(try_instruction("2,1,8,lo").is_none());
+        assert!(try_instruction("2,1,8,load,10-4-0000000a,14-8-00000").is_none());
+        assert!(try_instruction("2,1,8,load,10-4-0000").is_none());
+        assert!(try_instruction("2,1,8,load,10-x-0000").is_none());
+        assert!(try_instruction("2,1").is_none());
+    }
+}
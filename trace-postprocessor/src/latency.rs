@@ -0,0 +1,127 @@
+use comparison::*;
+use outcome::*;
+use propagation::*;
+use trace::*;
+use utils::*;
+
+// Detection latency of a faulty run, from the injection the first deviation
+// is attributed to.
+#[derive(Clone, Debug, Default)]
+pub struct Latency {
+    // events of all threads until the first deviating event
+    pub events: u64,
+    // events of the deviating thread until the first deviating event
+    pub thread_events: u64,
+    // timestamp units until the first deviating event
+    pub time: u64,
+    // timestamp units until the fault shows in the output or exit code
+    pub manifestation: Option<u64>,
+}
+
+pub const LATENCIES: [&str; 4] = [
+    "Latency-events",
+    "Latency-thread-events",
+    "Latency-time",
+    "Latency-manifestation",
+];
+
+fn get_position(tr: &SymbolicTrace, inst: usize) -> Option<usize> {
+    tr.events.binary_search_by_key(&inst, |ev| ev.inst).ok()
+}
+
+// The output manifests at the first output call from the deviation on, a
+// crash, hang or wrong exit code at the end of the trace.
+fn get_manifestation(tr: &SymbolicTrace, dev: usize, outcome: Outcome) -> Option<u64> {
+    match outcome {
+        Outcome::Sdc => tr.events[dev..]
+            .iter()
+            .find(|ev| is_output_call(ev))
+            .or(tr.events.last())
+            .map(|ev| get_long(&ev.timestamp)),
+        Outcome::Detected | Outcome::Crash | Outcome::Hang => {
+            tr.events.last().map(|ev| get_long(&ev.timestamp))
+        }
+        _ => None,
+    }
+}
+
+pub fn get_latency(tr: &SymbolicTrace, res: &ComparisonResult, outcome: Outcome) -> Option<Latency> {
+    let inj_ts = tr.injection.get(res.injection?)?.0;
+    let dev = get_position(tr, res.inst?)?;
+    let dev_ev = &tr.events[dev];
+    let inj = tr
+        .events
+        .iter()
+        .position(|ev| inj_ts < get_long(&ev.timestamp))
+        .unwrap_or(dev);
+    let dev_ts = get_long(&dev_ev.timestamp);
+    Some(Latency {
+        events: dev.saturating_sub(inj) as u64,
+        thread_events: tr.events[inj.min(dev)..dev]
+            .iter()
+            .filter(|ev| ev.thread == dev_ev.thread)
+            .count() as u64,
+        time: dev_ts.saturating_sub(inj_ts),
+        manifestation: get_manifestation(tr, dev, outcome).map(|ts| ts.saturating_sub(inj_ts)),
+    })
+}
+
+pub fn get_latency_record(latency: Option<&Latency>) -> Vec<String> {
+    match latency {
+        Some(l) => vec![
+            format!("{}", l.events),
+            format!("{}", l.thread_events),
+            format!("{}", l.time),
+            l.manifestation.map_or(String::new(), |m| format!("{}", m)),
+        ],
+        None => vec![String::new(); LATENCIES.len()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mask::*;
+    use testutils::*;
+    use tolerance::*;
+
+    #[test]
+    fn latency_from_the_attributed_injection() {
+        let a = get_object("a", 0x1000, 8);
+        let puts = EventType::Call {
+            name: String::from("puts"),
+            value: get_value(0),
+            args: Vec::new(),
+        };
+        let events = |val: u128| {
+            vec![
+                get_write("0", 1, &a, 0, 1),
+                get_write("0", 2, &a, 0, 1),
+                get_write("1", 3, &a, 0, 1),
+                get_write("0", 4, &a, 0, val),
+                Event::new(puts.clone(), "1", 5, "main"),
+                get_write("0", 6, &a, 0, 1),
+            ]
+        };
+        let golden = get_trace("bench_trace.0-0", events(1));
+        let mut faulty = get_trace("bench_trace.1-0", events(2));
+        faulty.injection.push((1, 2));
+        let res = compare_projection(
+            &golden,
+            &faulty,
+            "0",
+            &Tolerance::new(),
+            &CompareOptions::default(),
+            &Mask::new(),
+        );
+        assert_eq!((res.injection, res.inst), (Some(0), Some(3)));
+
+        let latency = get_latency(&faulty, &res, Outcome::Sdc).unwrap();
+        assert_eq!(get_latency_record(Some(&latency)), vec!["1", "0", "2", "3"]);
+        let latency = get_latency(&faulty, &res, Outcome::Crash).unwrap();
+        assert_eq!(latency.manifestation, Some(4));
+        let latency = get_latency(&faulty, &res, Outcome::MaskedInTrace).unwrap();
+        assert_eq!(latency.manifestation, None);
+        assert_eq!(get_latency_record(None), vec![""; 4]);
+    }
+}
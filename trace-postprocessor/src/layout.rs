@@ -0,0 +1,215 @@
+use object::*;
+
+// nested types deeper than this aren't resolved (guards against cycles)
+const MAX_DEPTH: usize = 16;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FieldLayout {
+    pub name: String,
+    pub offset: u64,
+    // size of a single element
+    pub size: u64,
+    #[serde(default = "get_one")]
+    pub count: u64,
+    // struct type of the field, none for scalars
+    #[serde(default, rename = "type")]
+    pub typ: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TypeLayout {
+    pub name: String,
+    pub size: u64,
+    #[serde(default)]
+    pub field: Vec<FieldLayout>,
+}
+
+// Type of the objects allocated at an instruction (stack or heap) or of a
+// global variable. Objects larger than their type are arrays of it.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ObjectLayout {
+    #[serde(default)]
+    pub site: Option<u32>,
+    #[serde(default)]
+    pub global: Option<String>,
+    // variable name, the object ID is used otherwise
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub typ: String,
+}
+
+// Type layout information extracted from the LLVM module or the DWARF
+// information of a benchmark, e.g.
+//
+// [[type]]
+// name = "struct.point"
+// size = 16
+// field = [{ name = "x", offset = 0, size = 8 }, { name = "y", offset = 8, size = 8 }]
+//
+// [[object]]
+// global = "points"
+// type = "struct.point"
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Layout {
+    #[serde(default, rename = "type")]
+    pub types: Vec<TypeLayout>,
+    #[serde(default, rename = "object")]
+    pub objects: Vec<ObjectLayout>,
+}
+
+fn get_one() -> u64 {
+    1
+}
+
+impl Layout {
+    pub fn new() -> Layout {
+        Layout::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    fn get_type(&self, name: &str) -> Option<&TypeLayout> {
+        self.types.iter().find(|t| t.name == name)
+    }
+
+    fn get_object(&self, obj: &Object) -> Option<&ObjectLayout> {
+        self.objects.iter().find(|o| {
+            o.global.as_ref().is_some_and(|g| *g == obj.id)
+                || o.site.is_some_and(|s| obj.identified && obj.inst_id == s)
+        })
+    }
+
+    fn resolve(&self, typ: &str, offset: u64, depth: usize, res: &mut String) {
+        let field = match self.get_type(typ) {
+            Some(t) if depth < MAX_DEPTH => t
+                .field
+                .iter()
+                .rev()
+                .find(|f| offset >= f.offset && offset < f.offset + f.size * f.count),
+            _ => None,
+        };
+        let f = match field {
+            Some(f) => f,
+            None => {
+                if offset != 0 {
+                    res.push_str(&format!("+{}", offset));
+                }
+                return;
+            }
+        };
+        res.push('.');
+        res.push_str(&f.name);
+        let mut rem = offset - f.offset;
+        if f.count > 1 && f.size > 0 {
+            res.push_str(&format!("[{}]", rem / f.size));
+            rem %= f.size;
+        }
+        match &f.typ {
+            Some(t) => self.resolve(t, rem, depth + 1, res),
+            None if rem != 0 => res.push_str(&format!("+{}", rem)),
+            None => {}
+        }
+    }
+
+    // Field path of a byte offset into an object, e.g. `points[3].x`.
+    pub fn get_path(&self, obj: &Object, offset: u64) -> Option<String> {
+        let o = self.get_object(obj)?;
+        let t = self.get_type(&o.typ)?;
+        let mut res = o.name.clone().unwrap_or_else(|| obj.id.clone());
+        let mut rem = offset;
+        if obj.size > t.size && t.size > 0 {
+            res.push_str(&format!("[{}]", offset / t.size));
+            rem %= t.size;
+        }
+        self.resolve(&t.name, rem, 0, &mut res);
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use testutils::*;
+    use toml;
+    use trace::*;
+
+    const LAYOUT: &str = r#"
+[[type]]
+name = "struct.point"
+size = 16
+field = [{ name = "x", offset = 0, size = 8 }, { name = "y", offset = 8, size = 8 }]
+
+[[type]]
+name = "struct.shape"
+size = 40
+field = [
+    { name = "id", offset = 0, size = 8 },
+    { name = "pts", offset = 8, size = 16, count = 2, type = "struct.point" },
+]
+
+[[object]]
+global = "points"
+type = "struct.point"
+
+[[object]]
+site = 7
+name = "s"
+type = "struct.shape"
+"#;
+
+    fn get_shape() -> Object {
+        let mut obj = (*get_object("0::7#1", 0x2000, 40)).clone();
+        obj.inst_id = 7;
+        obj
+    }
+
+    #[test]
+    fn offsets_are_resolved_into_field_paths() {
+        let layout: Layout = toml::from_str(LAYOUT).unwrap();
+        let points = get_object("points", 0x1000, 64);
+        assert_eq!(layout.get_path(&points, 56).unwrap(), "points[3].y");
+        assert_eq!(layout.get_path(&points, 52).unwrap(), "points[3].x+4");
+        let shape = get_shape();
+        assert_eq!(layout.get_path(&shape, 0).unwrap(), "s.id");
+        assert_eq!(layout.get_path(&shape, 24).unwrap(), "s.pts[1].x");
+        assert_eq!(layout.get_path(&shape, 36).unwrap(), "s.pts[1].y+4");
+        // only identified objects are matched by their allocation site
+        let mut guessed = get_shape();
+        guessed.identified = false;
+        assert!(layout.get_path(&guessed, 0).is_none());
+        assert!(layout
+            .get_path(&get_object("other", 0x3000, 8), 0)
+            .is_none());
+    }
+
+    #[test]
+    fn sanitized_llfi_trace_prints_field_paths() {
+        let points = get_object("points", 0x1000, 64);
+        let other = get_object("other", 0x3000, 8);
+        let pointer = |target: &Arc<Object>, offset| EventType::Write {
+            value: EventData::Pointer {
+                target: Arc::clone(target),
+                offset,
+            },
+            object: Arc::clone(&other),
+            offset: 0,
+            concrete: String::new(),
+        };
+        let mut tr = get_trace(
+            "bench_trace.0-0",
+            vec![
+                Event::new(pointer(&points, 24), "0", 1, "main"),
+                Event::new(pointer(&other, 4), "0", 2, "main"),
+            ],
+        );
+        tr.layout = Arc::new(toml::from_str(LAYOUT).unwrap());
+        let (_, sanitized) = tr.get_llfi_trace(u32::MAX).unwrap();
+        let lines = sanitized.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "ID: 1\tOPCode: \tValue: points[1].y");
+        assert_eq!(lines[1], "ID: 2\tOPCode: \tValue: other-4");
+    }
+}
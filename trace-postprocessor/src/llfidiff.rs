@@ -0,0 +1,277 @@
+use std::cmp::min;
+use std::error::Error;
+use std::fs::read_to_string;
+
+// maximal number of lines (golden and faulty together) that are skipped to
+// find the point at which the traces converge after a control deviation
+const RESYNC_WINDOW: usize = 1000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LlfiLine {
+    pub id: String,
+    pub op_code: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct LlfiDiff {
+    pub result: String,
+    pub details: Vec<String>,
+}
+
+impl LlfiLine {
+    pub fn from(line: &str) -> Option<LlfiLine> {
+        let mut id = None;
+        let mut op_code = None;
+        let mut value = None;
+        for field in line.split('\t') {
+            if let Some(v) = field.strip_prefix("ID: ") {
+                id = Some(v);
+            } else if let Some(v) = field.strip_prefix("OPCode: ") {
+                op_code = Some(v);
+            } else if let Some(v) = field.strip_prefix("Value: ") {
+                value = Some(v);
+            }
+        }
+        Some(LlfiLine {
+            id: String::from(id?),
+            op_code: String::from(op_code?),
+            value: String::from(value.unwrap_or("")),
+        })
+    }
+}
+
+impl LlfiDiff {
+    fn new() -> LlfiDiff {
+        LlfiDiff {
+            result: String::from("no-dev"),
+            details: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, kind: &str, detail: String) {
+        if self.result == "no-dev" {
+            self.result = String::from(kind);
+        }
+        self.details.push(format!("{}: {}", kind, detail));
+    }
+}
+
+// Returns the instruction number the trace starts at (1 if the trace has no
+// #TraceStartInstNumber header) and its lines.
+pub fn parse_llfi_trace(content: &str) -> Result<(usize, Vec<LlfiLine>), Box<Error>> {
+    let mut start = 1;
+    let mut lines = Vec::new();
+    for line in content.lines() {
+        if let Some(v) = line.strip_prefix("#TraceStartInstNumber: ") {
+            start = v.trim().parse()?;
+        } else if let Some(l) = LlfiLine::from(line) {
+            lines.push(l);
+        }
+    }
+    Ok((start, lines))
+}
+
+// Finds the closest pair of positions at which both traces execute the same
+// instruction again after a control flow deviation, at most RESYNC_WINDOW
+// lines apart.
+fn resync(golden: &[LlfiLine], faulty: &[LlfiLine]) -> Option<(usize, usize)> {
+    for dist in 1..min(golden.len() + faulty.len(), RESYNC_WINDOW + 1) {
+        for g in 0..dist + 1 {
+            let f = dist - g;
+            if g < golden.len() && f < faulty.len() && golden[g].id == faulty[f].id {
+                return Some((g, f));
+            }
+        }
+    }
+    None
+}
+
+// Compares the two traces from the start point of the faulty trace on, for at
+// most `span` lines (the window the faulty trace has been written for). The
+// result follows the classification of LLFI's tracediff: no-dev, control-dev
+// (different instruction sequence, including a trace that ends before the
+// other one) or data-dev (same instructions, different values); the details
+// contain all deviating blocks.
+pub fn diff_llfi_traces(golden: &str, faulty: &str, span: usize) -> Result<LlfiDiff, Box<Error>> {
+    let (g_start, g_lines) = parse_llfi_trace(golden)?;
+    let (f_start, f_lines) = parse_llfi_trace(faulty)?;
+    let mut res = LlfiDiff::new();
+    if f_start < g_start || f_start - g_start > g_lines.len() {
+        res.result = String::from("error");
+        res.details.push(format!(
+            "golden trace (from {} with {} lines) doesn't cover the start point {}",
+            g_start,
+            g_lines.len(),
+            f_start
+        ));
+        return Ok(res);
+    }
+    let golden = &g_lines[f_start - g_start..];
+    let golden = &golden[..min(golden.len(), span)];
+    let f_lines = &f_lines[..min(f_lines.len(), span)];
+    let (mut g, mut f) = (0, 0);
+    let mut converged = true;
+    while g < golden.len() && f < f_lines.len() {
+        if golden[g].id != f_lines[f].id {
+            match resync(&golden[g..], &f_lines[f..]) {
+                Some((dg, df)) => {
+                    res.add(
+                        "control-dev",
+                        format!(
+                            "golden {} (+{}) faulty {} (+{})",
+                            f_start + g,
+                            dg,
+                            f_start + f,
+                            df
+                        ),
+                    );
+                    g += dg;
+                    f += df;
+                }
+                None => {
+                    res.add(
+                        "control-dev",
+                        format!(
+                            "golden {} faulty {} never converge",
+                            f_start + g,
+                            f_start + f
+                        ),
+                    );
+                    converged = false;
+                    break;
+                }
+            }
+            continue;
+        }
+        if golden[g].value != f_lines[f].value {
+            res.add(
+                "data-dev",
+                format!(
+                    "golden {} faulty {} ID {} {}: {} != {}",
+                    f_start + g,
+                    f_start + f,
+                    golden[g].id,
+                    golden[g].op_code,
+                    golden[g].value,
+                    f_lines[f].value
+                ),
+            );
+        }
+        g += 1;
+        f += 1;
+    }
+    // a trace that ends before the other one is a control deviation, unless
+    // the faulty trace only ends with its window
+    if converged && f < f_lines.len() {
+        res.add(
+            "control-dev",
+            format!(
+                "faulty continues for {} lines after the end of golden at {}",
+                f_lines.len() - f,
+                f_start + g
+            ),
+        );
+    } else if converged && g < golden.len() && f_lines.len() < span {
+        res.add(
+            "control-dev",
+            format!(
+                "faulty ends at {} before golden {} (+{})",
+                f_start + f,
+                f_start + g,
+                golden.len() - g
+            ),
+        );
+    }
+    Ok(res)
+}
+
+pub fn diff_llfi_files(g_file: &str, f_file: &str, span: usize) -> Result<LlfiDiff, Box<Error>> {
+    let golden = read_to_string(g_file)?;
+    let faulty = read_to_string(f_file)?;
+    diff_llfi_traces(&golden, &faulty, span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // LLFI trace of (ID, value) lines starting at instruction `start`
+    fn get_trace(start: usize, lines: &[(u32, u32)]) -> String {
+        let mut res = format!("#TraceStartInstNumber: {}\n", start);
+        for (id, val) in lines.iter() {
+            res += &format!("ID: {}\tOPCode: load\tValue: {}\n", id, val);
+        }
+        res
+    }
+
+    #[test]
+    fn lines_are_parsed() {
+        let line = LlfiLine::from("ID: 3\tOPCode: store\tValue: 7\tLocation: a.c:4").unwrap();
+        assert_eq!(line.id, "3");
+        assert_eq!(line.op_code, "store");
+        assert_eq!(line.value, "7");
+        assert!(LlfiLine::from("#TraceStartInstNumber: 3").is_none());
+        let (start, lines) = parse_llfi_trace(&get_trace(4, &[(1, 0), (2, 0)])).unwrap();
+        assert_eq!(start, 4);
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn data_and_control_deviations() {
+        let golden = get_trace(1, &[(1, 0), (2, 0), (3, 0), (4, 0), (5, 0)]);
+        let same = get_trace(3, &[(3, 0), (4, 0), (5, 0)]);
+        assert_eq!(
+            diff_llfi_traces(&golden, &same, 10).unwrap().result,
+            "no-dev"
+        );
+        let data = get_trace(3, &[(3, 0), (4, 1), (5, 0)]);
+        let diff = diff_llfi_traces(&golden, &data, 10).unwrap();
+        assert_eq!(diff.result, "data-dev");
+        assert_eq!(diff.details.len(), 1);
+        let control = get_trace(3, &[(3, 0), (7, 1), (5, 0)]);
+        let diff = diff_llfi_traces(&golden, &control, 10).unwrap();
+        assert_eq!(diff.result, "control-dev");
+        assert_eq!(diff.details.len(), 1);
+        let outside = get_trace(9, &[(3, 0)]);
+        assert_eq!(
+            diff_llfi_traces(&golden, &outside, 10).unwrap().result,
+            "error"
+        );
+    }
+
+    #[test]
+    fn tails_are_control_deviations() {
+        let golden = get_trace(1, &[(1, 0), (2, 0), (3, 0), (4, 0)]);
+        let early = get_trace(2, &[(2, 0), (3, 0)]);
+        let diff = diff_llfi_traces(&golden, &early, 10).unwrap();
+        assert_eq!(diff.result, "control-dev");
+        let longer = get_trace(2, &[(2, 0), (3, 0), (4, 0), (5, 0)]);
+        let diff = diff_llfi_traces(&golden, &longer, 10).unwrap();
+        assert_eq!(diff.result, "control-dev");
+        // the faulty trace only ends with its window
+        assert_eq!(
+            diff_llfi_traces(&golden, &early, 2).unwrap().result,
+            "no-dev"
+        );
+    }
+
+    #[test]
+    fn resync_is_bounded() {
+        let golden = (0..RESYNC_WINDOW as u32 + 2)
+            .map(|i| LlfiLine {
+                id: format!("{}", i),
+                op_code: String::from("load"),
+                value: String::new(),
+            })
+            .collect::<Vec<_>>();
+        let mut faulty = vec![LlfiLine {
+            id: String::from("x"),
+            op_code: String::from("load"),
+            value: String::new(),
+        }];
+        faulty.extend_from_slice(&golden[golden.len() - 1..]);
+        assert_eq!(resync(&golden, &faulty), None);
+        assert_eq!(resync(&golden[golden.len() - 3..], &faulty), Some((2, 1)));
+    }
+}
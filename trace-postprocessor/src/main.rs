@@ -1,5 +1,6 @@
 #![macro_use]
 extern crate argparse;
+extern crate cpp_demangle;
 extern crate badlog;
 extern crate csv;
 extern crate log;
@@ -7,27 +8,68 @@ extern crate pbr;
 extern crate petgraph;
 extern crate rayon;
 extern crate rsmt2;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
 
 mod comparison;
+mod crash;
 mod fileio;
+mod heap;
 mod instruction;
+mod latency;
+mod layout;
+mod llfidiff;
+mod mask;
+mod memcheck;
+mod memmap;
 //mod mc;
 mod error;
 mod nondet;
 mod object;
+mod outcome;
+mod propagation;
+mod provenance;
+mod rules;
+mod slice;
 mod smt;
+mod source;
+mod stack;
+mod symbols;
+mod summary;
+#[cfg(test)]
+mod testutils;
+mod tls;
+mod tolerance;
 mod trace;
 mod utils;
+mod value;
+mod vulnerability;
 
 use argparse::{ArgumentParser, Store, StoreOption, StoreTrue};
 use comparison::*;
+use crash::*;
 #[allow(unused_imports)]
 use log::*;
 use pbr::ProgressBar;
 //use mc::*;
 use error::SanError;
 use fileio::*;
-use nondet::is_deterministic;
+use heap::*;
+use latency::*;
+use layout::Layout;
+use llfidiff::*;
+use mask::Mask;
+use memcheck::*;
+use nondet::{get_race_header, is_deterministic};
+use outcome::*;
+use propagation::*;
+use rules::Rules;
+use source::SourceMap;
+use symbols::SymbolMap;
+use slice::*;
 use rayon::prelude::*;
 use std::cmp::min;
 use std::env::var_os;
@@ -37,6 +79,9 @@ use std::path::Path;
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use summary::*;
+use tolerance::Tolerance;
+use vulnerability::*;
 use trace::*;
 // Not needed for now...
 //fn get_dec<T: Integer + Unsigned>(number: &str) -> T {
@@ -48,18 +93,62 @@ fn format_time(val: &Duration) -> String {
     format!("{}", (sec + nano) as u128)
 }
 
+// Result record of a faulty run, see RESULT_COLUMNS. The details are only
+// known for the comparison of the symbolic traces.
+fn get_record(
+    golden: &SymbolicTrace,
+    faulty: &SymbolicTrace,
+    deviation: String,
+    comp_time: &Duration,
+    res: Option<&ComparisonResult>,
+) -> Vec<String> {
+    let inf = faulty.get_id_info();
+    let outcome = Outcome::classify(golden, faulty, &deviation);
+    let mut rec = vec![
+        inf.0,
+        inf.1,
+        inf.2,
+        deviation,
+        format!("{}", faulty.is_output_deviating(golden)),
+        faulty.ret_code.clone(),
+        (faulty.is_injected()).to_string(),
+        format_time(&faulty.symb_time),
+        format_time(comp_time),
+        format!("{}", res.map_or(0, |r| r.tolerated)),
+        format!("{}", res.map_or(0, |r| r.suppressed)),
+        String::from(outcome.name()),
+        res.and_then(|r| r.injection)
+            .map_or(String::new(), |i| format!("{}", i)),
+        res.map_or(String::new(), |r| r.id.clone()),
+        res.map_or(String::new(), |r| r.field.clone()),
+        res.map_or(String::new(), |r| faulty.get_location(&r.id)),
+    ];
+    let latency = res.and_then(|r| get_latency(faulty, r, outcome));
+    rec.extend(get_latency_record(latency.as_ref()));
+    rec.push(format!("{}", faulty.truncated));
+    rec
+}
+
 fn do_comparison(
     golden_run: String,
     faulty_runs: Vec<String>,
     nondet: bool,
-    benchmark: &str
+    benchmark: &str,
+    tol: &Tolerance,
+    opts: &CompareOptions,
+    mask: &Mask,
+    rules: &Rules,
+    layout: &Arc<Layout>,
+    sources: &Arc<SourceMap>,
+    symbols: &SymbolMap,
 ) -> Result<(SymbolicTrace, Vec<SymbolicTrace>, Vec<Vec<String>>), Box<Error>> {
     let mut pb = Arc::new(Mutex::new(ProgressBar::new(faulty_runs.len() as u64 + 1)));
-    let mut golden_trace = SymbolicTrace::from(&*golden_run)?;
+    let mut golden_trace = SymbolicTrace::from(&*golden_run, rules, layout, sources, symbols)?;
     warn!("Golden trace has been build...");
     if nondet {
         let start = Instant::now();
-        let (result, num_deps, g_evs, g_objs, solving_time) = is_deterministic(&mut golden_trace, benchmark);
+        let (result, num_deps, g_evs, g_objs, solving_time, races) =
+            is_deterministic(&mut golden_trace, benchmark);
         let end = start.elapsed();
         // create result record
         // TODO: Add the number of objects involved in a global dependency
@@ -73,6 +162,10 @@ fn do_comparison(
         write_check_results(&*format!("{}.reversibility-check.csv", benchmark), res_record)?;        
         if !result{
             warn!("Golden trace is not deterministic...");
+            let race_f = format!("{}.races", benchmark);
+            if let Err(e) = write_table(&race_f, &get_race_header(), races, true, false) {
+                warn!("Couldn't write the races to {}: {}", race_f, e);
+            }
             info!("Stopping the comparison!");
             return Err(Box::new(SanError::new("Goldenrun is not deterministic")));
         } else {
@@ -90,7 +183,7 @@ fn do_comparison(
             pb.lock().unwrap().inc();
 
             warn!("Dealing with {}", _f);
-            if let Ok(tr) = SymbolicTrace::from(_f) {
+            if let Ok(tr) = SymbolicTrace::from(_f, rules, layout, sources, symbols) {
                 return Some(tr);
             } else {
                 return None;
@@ -107,21 +200,9 @@ fn do_comparison(
         .map(|_f| {
             warn!("Start comparison...");
             let start = Instant::now();
-            let res = compare_naive(&golden_trace, _f);
+            let res = compare_naive(&golden_trace, _f, tol, opts, mask);
             let end = start.elapsed();
-            let inf = _f.get_id_info();
-            let mut rec = Vec::new();
-            rec.extend_from_slice(&[
-                inf.0,
-                inf.1,
-                inf.2,
-                res.clone(),
-                format!("{}", _f.output_hash != golden_trace.output_hash),
-                _f.ret_code.clone(),
-                (_f.is_injected()).to_string(),
-                format_time(&_f.symb_time),
-                format_time(&end),
-            ]);
+            let rec = get_record(&golden_trace, _f, res.deviation.clone(), &end, Some(&res));
             pb.lock().unwrap().inc();
             return rec;
         })
@@ -132,6 +213,40 @@ fn do_comparison(
     Ok((golden_trace, faulty_traces, results))
 }
 
+fn do_learning(
+    golden_run: String,
+    golden_runs: Vec<String>,
+    rules: &Rules,
+) -> Result<Mask, Box<Error>> {
+    let pb = Arc::new(Mutex::new(ProgressBar::new(golden_runs.len() as u64 + 1)));
+    // the mask only depends on the instruction IDs
+    let layout = Arc::new(Layout::new());
+    let sources = Arc::new(SourceMap::new());
+    let symbols = SymbolMap::new();
+    let reference = SymbolicTrace::from(&*golden_run, rules, &layout, &sources, &symbols)?;
+    pb.lock().unwrap().inc();
+    let traces = golden_runs
+        .par_iter()
+        .filter_map(|_f| {
+            pb.lock().unwrap().inc();
+            warn!("Dealing with {}", _f);
+            SymbolicTrace::from(_f, rules, &layout, &sources, &symbols).ok()
+        })
+        .filter(|tr| {
+            if tr.truncated {
+                warn!("Not learning from the truncated trace {}", tr.id);
+            }
+            !tr.truncated
+        })
+        .collect::<Vec<_>>();
+    pb.lock().unwrap().finish_print("Done!");
+    if traces.is_empty() {
+        return Err(Box::new(SanError::new("No golden runs to learn from")));
+    }
+    warn!("Learning the mask from {} golden runs...", traces.len() + 1);
+    Ok(Mask::learn(&reference, &traces))
+}
+
 fn exec_diff_script(g_file: &str, f_file: &str) -> Option<i32> {
     let script = "../llfi/tools/tracediff.py";
     if let Ok(stat) = Command::new(script)
@@ -156,6 +271,65 @@ fn get_result_code(code: Option<i32>) -> String {
     }
 }
 
+// Diffs the two llfi traces natively, the details are written next to the
+// faulty trace. The tracediff script is only run as an optional cross-check.
+fn do_llfi_diff(g_file: &str, f_file: &str, span: u32, is_pydiff: bool) -> Option<LlfiDiff> {
+    let diff = match diff_llfi_files(g_file, f_file, span as usize) {
+        Ok(d) => d,
+        Err(e) => {
+            warn!("Couldn't diff the llfi trace {}: {}", f_file, e);
+            return None;
+        }
+    };
+    if let Err(e) = write_to_file(&*diff.details.join("\n"), &*format!("{}-diff", f_file)) {
+        warn!("Couldn't write the diff details: {}", e);
+    }
+    if is_pydiff {
+        let py_res = get_result_code(exec_diff_script(g_file, f_file));
+        if py_res != diff.result {
+            warn!(
+                "tracediff.py reports {} instead of {} for {}",
+                py_res, diff.result, f_file
+            );
+        }
+    }
+    Some(diff)
+}
+
+// Summarizes the result tables of all comparison methods that have been
+// written for the benchmark so far.
+fn do_summary(base_dir: &str, benchmark: &str, is_fi: i32) {
+    let mode = if is_fi >= 0 { "fi" } else { "gr" };
+    let tables = [
+        ("sanitized", format!("{}/{}/results/{}-results.csv", base_dir, mode, benchmark)),
+        (
+            "llfi-raw",
+            format!("{}/{}/llfi-results/{}-llfi-results.csv", base_dir, mode, benchmark),
+        ),
+        (
+            "llfi-symb",
+            format!(
+                "{}/{}/llfi-symb-results/{}-llfi-symb-results.csv",
+                base_dir, mode, benchmark
+            ),
+        ),
+    ];
+    let mut summary = Vec::new();
+    for (method, f) in tables.iter() {
+        if !Path::new(&**f).exists() {
+            continue;
+        }
+        match read_results(f) {
+            Ok(results) => summary.extend(summarize(method, &results)),
+            Err(e) => warn!("Couldn't load the results {}: {}", f, e),
+        }
+    }
+    let summary_f = format!("{}/{}/results/{}-summary", base_dir, mode, benchmark);
+    if let Err(e) = write_summary(&summary_f, &summary) {
+        warn!("Couldn't write the summary to {}: {}", summary_f, e);
+    }
+}
+
 fn get_files(dir: &Vec<String>, fm: i32, is_fi: i32, max_comp: Option<u32>, offset: u32) -> Vec<String> {
     let mut files = Vec::new();
     if is_fi >= 0 {
@@ -190,8 +364,11 @@ fn get_files(dir: &Vec<String>, fm: i32, is_fi: i32, max_comp: Option<u32>, offs
     }
     return files[offset as usize..offset as usize + m].to_vec();
 }
-fn start(
-    benchmark: &str,
+
+// Options of a run, see the argument parser in main.
+#[derive(Clone, Debug)]
+struct Config {
+    benchmark: String,
     is_fi: i32,
     is_seq: bool,
     is_llfi_comp: bool,
@@ -200,8 +377,89 @@ fn start(
     max_comp: Option<u32>,
     max_span: Option<u32>,
     nondet: bool,
-    offset: u32
-) {
+    offset: u32,
+    tol_file: Option<String>,
+    is_call_context: bool,
+    mask_file: Option<String>,
+    is_learn: bool,
+    rules_file: Option<String>,
+    is_pydiff: bool,
+    is_summary: bool,
+    is_propagation: bool,
+    is_slice: bool,
+    slice_event: Option<usize>,
+    layout_file: Option<String>,
+    source_file: Option<String>,
+    symbol_file: Option<String>,
+    is_memmap: bool,
+    memmap_event: Option<usize>,
+    memmap_addr: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            benchmark: String::new(),
+            is_fi: -1,
+            is_seq: false,
+            is_llfi_comp: false,
+            is_overwrite: false,
+            is_append: false,
+            max_comp: None,
+            max_span: None,
+            nondet: false,
+            offset: 0,
+            tol_file: None,
+            is_call_context: false,
+            mask_file: None,
+            is_learn: false,
+            rules_file: None,
+            is_pydiff: false,
+            is_summary: false,
+            is_propagation: false,
+            is_slice: false,
+            slice_event: None,
+            layout_file: None,
+            source_file: None,
+            symbol_file: None,
+            is_memmap: false,
+            memmap_event: None,
+            memmap_addr: None,
+        }
+    }
+}
+
+fn start(cfg: &Config) {
+    let Config {
+        ref benchmark,
+        is_fi,
+        is_seq,
+        is_llfi_comp,
+        is_overwrite,
+        is_append,
+        max_comp,
+        max_span,
+        nondet,
+        offset,
+        ref tol_file,
+        is_call_context,
+        ref mask_file,
+        is_learn,
+        ref rules_file,
+        is_pydiff,
+        is_summary,
+        is_propagation,
+        is_slice,
+        slice_event,
+        ref layout_file,
+        ref source_file,
+        ref symbol_file,
+        is_memmap,
+        memmap_event,
+        ref memmap_addr,
+    } = *cfg;
+    // the mask is always learned from the golden runs
+    let is_fi = if is_learn { -1 } else { is_fi };
     let mut dir = String::from("ressources");
     if let Some(v) = var_os("VDATA") {
         dir = v.into_string().unwrap();
@@ -216,6 +474,11 @@ fn start(
     base_dir += "-";
     base_dir += seq_or_pth;
 
+    if is_summary {
+        do_summary(&base_dir, benchmark, is_fi);
+        return;
+    }
+
     let mut g_dir = String::from(&*base_dir);
     g_dir += "/gr/raw-traces";
 
@@ -260,9 +523,328 @@ fn start(
         faulty_runs.extend_from_slice(&*get_files(&names, 0, is_fi, max_comp, offset));
     }
 
+    let mut rules = Rules::new();
+    if let Some(f) = rules_file {
+        match read_rules(&*f) {
+            Ok(r) => rules = r,
+            Err(e) => warn!("Couldn't load the rules {}: {}", f, e),
+        }
+    }
+
+    if is_learn {
+        let mask_f = format!("{}/gr/{}-mask.csv", base_dir, benchmark);
+        match do_learning(golden_run, faulty_runs, &rules) {
+            Ok(mask) => {
+                if let Err(e) = write_mask(&*mask_f, &mask) {
+                    warn!("Couldn't write the mask to {}: {}", mask_f, e);
+                }
+            }
+            Err(e) => warn!("Learning was unsuccessful: {}", e),
+        }
+        return;
+    }
+
+    let mut mask = Mask::new();
+    if let Some(f) = mask_file {
+        match read_mask(&*f) {
+            Ok(m) => mask = m,
+            Err(e) => warn!("Couldn't load the mask {}: {}", f, e),
+        }
+        if mask.is_empty() {
+            warn!("The mask {} is empty", f);
+        }
+    }
+
+    let mut layout = Layout::new();
+    if let Some(f) = layout_file {
+        match read_layout(&*f) {
+            Ok(l) => layout = l,
+            Err(e) => warn!("Couldn't load the type layouts {}: {}", f, e),
+        }
+        if layout.is_empty() {
+            warn!("The type layouts {} are empty", f);
+        }
+    }
+    let layout = Arc::new(layout);
+
+    let mut sources = SourceMap::new();
+    if let Some(f) = source_file {
+        match read_source_map(&*f) {
+            Ok(s) => sources = s,
+            Err(e) => warn!("Couldn't load the source locations {}: {}", f, e),
+        }
+        if sources.is_empty() {
+            warn!("The source locations {} are empty", f);
+        }
+    }
+    let sources = Arc::new(sources);
+
+    let mut symbols = SymbolMap::new();
+    if let Some(f) = symbol_file {
+        match read_symbol_map(&*f) {
+            Ok(s) => symbols = s,
+            Err(e) => warn!("Couldn't load the symbols {}: {}", f, e),
+        }
+        if symbols.is_empty() {
+            warn!("The symbols {} are empty", f);
+        }
+    }
+
+    let mut tol = Tolerance::new();
+    if let Some(f) = tol_file {
+        match read_tolerance(&*f) {
+            Ok(t) => tol = t,
+            Err(e) => warn!("Couldn't load the tolerance rules {}: {}", f, e),
+        }
+    }
+    let opts = CompareOptions {
+        call_context: is_call_context,
+    };
+
     if let Ok((mut golden_trace, mut faulty_traces, result)) =
-        do_comparison(golden_run, faulty_runs, nondet, benchmark)
+        do_comparison(
+            golden_run,
+            faulty_runs,
+            nondet,
+            benchmark,
+            &tol,
+            &opts,
+            &mask,
+            &rules,
+            &layout,
+            &sources,
+            &symbols,
+        )
     {
+        if is_fi >= 0 {
+            let records = faulty_traces
+                .par_iter()
+                .flat_map(|_f| {
+                    let inf = _f.get_id_info();
+                    get_injection_reports(&golden_trace, _f, &tol, &opts, &mask)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, r)| {
+                            vec![
+                                inf.0.clone(),
+                                inf.1.clone(),
+                                inf.2.clone(),
+                                format!("{}", i),
+                                format!("{}", r.timestamp),
+                                format!("{}", r.instruction),
+                                _f.get_location(&format!("{}", r.instruction)),
+                                format!("{}", r.is_activated()),
+                                r.latency.map_or(String::new(), |l| format!("{}", l)),
+                                r.deviation,
+                            ]
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let inj_f = format!("{}/fi/results/{}-injections", base_dir, benchmark);
+            if let Err(e) = write_table(
+                &*inj_f,
+                &[
+                    "Benchmark",
+                    "Fault-model",
+                    "#",
+                    "Injection",
+                    "Timestamp",
+                    "Instruction",
+                    "Location",
+                    "Activated",
+                    "Latency",
+                    "Deviation",
+                ],
+                records,
+                is_overwrite,
+                is_append,
+            ) {
+                warn!("Couldn't write the injections to {}: {}", inj_f, e);
+            }
+        }
+
+        if is_fi >= 0 {
+            let mut profile = VulnerabilityProfile::new();
+            for (_f, rec) in faulty_traces.iter().zip(result.iter()) {
+                profile.add_run(&golden_trace, _f, rec);
+            }
+            let vuln_f = format!("{}/fi/results/{}-vulnerability", base_dir, benchmark);
+            if let Err(e) = write_table(
+                &*vuln_f,
+                &*get_ranking_header(),
+                profile.get_ranking(),
+                is_overwrite,
+                is_append,
+            ) {
+                warn!("Couldn't write the vulnerability profile to {}: {}", vuln_f, e);
+            }
+            let vuln_func_f = format!("{}-functions", vuln_f);
+            if let Err(e) = write_table(
+                &*vuln_func_f,
+                &*get_functions_header(),
+                profile.get_functions(),
+                is_overwrite,
+                is_append,
+            ) {
+                warn!("Couldn't write the vulnerability profile to {}: {}", vuln_func_f, e);
+            }
+        }
+
+        if is_fi >= 0 {
+            let records = faulty_traces
+                .par_iter()
+                .filter_map(|_f| get_crash_report(_f).map(|c| get_crash_record(_f, &c)))
+                .collect::<Vec<_>>();
+            let crash_f = format!("{}/fi/results/{}-crashes", base_dir, benchmark);
+            if let Err(e) = write_table(
+                &*crash_f,
+                &*get_crash_header(),
+                records,
+                is_overwrite,
+                is_append,
+            ) {
+                warn!("Couldn't write the crash sites to {}: {}", crash_f, e);
+            }
+        }
+
+        {
+            let mut records = get_memcheck_records(&golden_trace);
+            records.extend(
+                faulty_traces
+                    .par_iter()
+                    .flat_map(get_memcheck_records)
+                    .collect::<Vec<_>>(),
+            );
+            let mem_f = format!(
+                "{}/{}/results/{}-memcheck",
+                base_dir,
+                if is_fi >= 0 { "fi" } else { "gr" },
+                benchmark
+            );
+            if let Err(e) = write_table(
+                &*mem_f,
+                &*get_memcheck_header(),
+                records,
+                is_overwrite,
+                is_append,
+            ) {
+                warn!("Couldn't write the memory violations to {}: {}", mem_f, e);
+            }
+        }
+
+        {
+            let mut records = get_heap_records(&golden_trace);
+            records.extend(
+                faulty_traces
+                    .par_iter()
+                    .flat_map(get_heap_records)
+                    .collect::<Vec<_>>(),
+            );
+            let heap_f = format!(
+                "{}/{}/results/{}-heap",
+                base_dir,
+                if is_fi >= 0 { "fi" } else { "gr" },
+                benchmark
+            );
+            if let Err(e) = write_table(
+                &*heap_f,
+                &*get_heap_header(),
+                records,
+                is_overwrite,
+                is_append,
+            ) {
+                warn!("Couldn't write the heap errors to {}: {}", heap_f, e);
+            }
+        }
+
+        if is_memmap {
+            let mut entries = golden_trace.get_memory_map(memmap_event);
+            entries.extend(
+                faulty_traces
+                    .par_iter()
+                    .flat_map(|_f| _f.get_memory_map(memmap_event))
+                    .collect::<Vec<_>>(),
+            );
+            let memmap_f = format!(
+                "{}/{}/results/{}-memmap",
+                base_dir,
+                if is_fi >= 0 { "fi" } else { "gr" },
+                benchmark
+            );
+            if let Err(e) = write_memmap(&*memmap_f, &entries, is_overwrite) {
+                warn!("Couldn't write the memory map to {}: {}", memmap_f, e);
+            }
+        }
+
+        if let Some(addr) = memmap_addr.as_ref() {
+            match u64::from_str_radix(addr.trim_start_matches("0x"), 16) {
+                Ok(address) => {
+                    let event = memmap_event.unwrap_or(usize::MAX);
+                    for _t in Some(&golden_trace).into_iter().chain(faulty_traces.iter()) {
+                        match _t.get_object_at(address, event) {
+                            Some(o) => println!("{}: {} is in {} at offset {}", _t.id, addr, o.id, address - o.address),
+                            None => println!("{}: {} isn't in any object", _t.id, addr),
+                        }
+                    }
+                }
+                Err(e) => warn!("Invalid address {}: {}", addr, e),
+            }
+        }
+
+        if is_propagation {
+            let records = faulty_traces
+                .par_iter()
+                .map(|_f| {
+                    let prop = track_propagation(&golden_trace, _f, &tol, &opts, &mask);
+                    get_propagation_record(_f, &prop)
+                })
+                .collect::<Vec<_>>();
+            let prop_f = format!(
+                "{}/{}/results/{}-propagation",
+                base_dir,
+                if is_fi >= 0 { "fi" } else { "gr" },
+                benchmark
+            );
+            if let Err(e) = write_table(
+                &*prop_f,
+                &*get_propagation_header(),
+                records,
+                is_overwrite,
+                is_append,
+            ) {
+                warn!("Couldn't write the propagation to {}: {}", prop_f, e);
+            }
+        }
+
+        if is_slice || slice_event.is_some() {
+            let slice_dir = format!(
+                "{}/{}/slices",
+                base_dir,
+                if is_fi >= 0 { "fi" } else { "gr" }
+            );
+            if let Err(e) = fs::create_dir_all(&*slice_dir) {
+                warn!("Couldn't create {}: {}", slice_dir, e);
+            }
+            faulty_traces.par_iter().for_each(|_f| {
+                // by default the slice starts at the first event the fault reached
+                let criterion = slice_event.or_else(|| {
+                    let prop = track_propagation(&golden_trace, _f, &tol, &opts, &mask);
+                    prop.steps
+                        .iter()
+                        .find(|s| s.dependency.is_fault())
+                        .map(|s| s.event)
+                });
+                if let Some(c) = criterion {
+                    let slice = backward_slice(_f, c);
+                    let slice_f = format!("{}/{}-slice", slice_dir, _f.id);
+                    if let Err(e) = write_slice(&*slice_f, _f, &slice) {
+                        warn!("Couldn't write the slice to {}: {}", slice_f, e);
+                    }
+                }
+            });
+        }
+
         let mut llfi_dir = String::from(&*base_dir);
         llfi_dir += "/";
         llfi_dir += if is_fi >= 0 { "fi" } else { "gr" };
@@ -336,47 +918,17 @@ fn start(
                     }
 
                     let start = Instant::now();
-                    let status = exec_diff_script(&*golden_llfi, &*faulty_llfi);
+                    let diff = do_llfi_diff(&*golden_llfi, &*faulty_llfi, tmp_span, is_pydiff);
                     let end = start.elapsed();
-                    if let None = status {
-                        warn!("llfi diff script crashed...");
-                        return None;
-                    }
-                    let inf = _f.get_id_info();
-                    let mut llfi_result = Vec::new();
-                    llfi_result.extend_from_slice(&[
-                        inf.0,
-                        inf.1,
-                        inf.2,
-                        get_result_code(status),
-                        format!("{}", _f.output_hash != golden_trace.output_hash),
-                        _f.ret_code.clone(),
-                        (_f.is_injected()).to_string(),
-                        format_time(&_f.symb_time),
-                        format_time(&end),
-                    ]);
+                    let diff = diff?;
+                    let llfi_result = get_record(&golden_trace, _f, diff.result, &end, None);
                     pb.lock().unwrap().inc();
 
                     let start = Instant::now();
-                    let status = exec_diff_script(&*golden_llfi_symb, &*faulty_llfi_symb);
+                    let symb_diff = do_llfi_diff(&*golden_llfi_symb, &*faulty_llfi_symb, tmp_span, is_pydiff);
                     let end = start.elapsed();
-                    if let None = status {
-                        warn!("llfi diff script crashed...");
-                    }
-                    let inf = _f.get_id_info();
-
-                    let mut llfi_symb_result = Vec::new();
-                    llfi_symb_result.extend_from_slice(&[
-                        inf.0,
-                        inf.1,
-                        inf.2,
-                        get_result_code(status),
-                        format!("{}", _f.output_hash != golden_trace.output_hash),
-                        _f.ret_code.clone(),
-                        (_f.is_injected()).to_string(),
-                        format_time(&_f.symb_time),
-                        format_time(&end),
-                    ]);
+                    let symb_res = symb_diff.map_or(String::from("error"), |d| d.result);
+                    let llfi_symb_result = get_record(&golden_trace, _f, symb_res, &end, None);
                     pb.lock().unwrap().inc();
                     return Some((llfi_result, llfi_symb_result));
                 })
@@ -417,9 +969,26 @@ fn start(
         result_f += benchmark;
         result_f += "-results";
 
+        let outcomes = summarize_outcomes(&result);
         if let Err(e) = write_results(&*result_f, result, is_overwrite, is_append) {
             warn!("Couldn't write the results to {}: {}", e, result_f);
         }
+
+        let outcome_f = format!(
+            "{}/{}/results/{}-outcomes",
+            base_dir,
+            if is_fi >= 0 { "fi" } else { "gr" },
+            benchmark
+        );
+        if let Err(e) = write_table(
+            &*outcome_f,
+            &*get_outcome_header(),
+            outcomes,
+            is_overwrite,
+            is_append,
+        ) {
+            warn!("Couldn't write the outcomes to {}: {}", outcome_f, e);
+        }
     } else {
         warn!("Comparison was unsuccessful...");
     }
@@ -428,60 +997,120 @@ fn main() {
     badlog::init(Some("Warn"));
 
     info!("Starting the refactored version...");
-    let mut benchmark = String::new();
     //let mut num: Option<u32> = Some(1000);
     //let mut f_num: Option<u32> = Some(6);
-    let mut max_comp: Option<u32> = None;
-    let mut max_span: Option<u32> = None;
-    let mut is_fi = -1;
-    let mut is_seq = false;
-    let mut is_llfi_comp = false;
-    let mut is_overwrite = false;
-    let mut is_append = false;
-    let mut nondet = false;
-    let mut offset = 0;
+    let mut cfg = Config::default();
     {
         let mut ap = ArgumentParser::new();
         ap.set_description("Trace postprocessor.");
-        ap.refer(&mut benchmark)
+        ap.refer(&mut cfg.benchmark)
             .add_argument("benchmark", Store, "benchmark to process");
-        ap.refer(&mut max_comp).add_argument(
+        ap.refer(&mut cfg.max_comp).add_argument(
             "repetitions",
             StoreOption,
             "maximal number of repetitions",
         );
-        ap.refer(&mut max_span)
+        ap.refer(&mut cfg.max_span)
             .add_argument("span", StoreOption, "maximal diff span");
-        ap.refer(&mut is_fi)
+        ap.refer(&mut cfg.is_fi)
             .add_option(&["-f", "--fi"], Store, "compare with fault injection");
-        ap.refer(&mut is_seq)
+        ap.refer(&mut cfg.is_seq)
             .add_option(&["-s", "--seq"], StoreTrue, "compare sequential traces");
-        ap.refer(&mut is_llfi_comp)
+        ap.refer(&mut cfg.is_llfi_comp)
             .add_option(&["-l", "--llfi"], StoreTrue, "compare with llfi");
-        ap.refer(&mut is_overwrite).add_option(
+        ap.refer(&mut cfg.is_overwrite).add_option(
             &["-o", "--ov"],
             StoreTrue,
             "overwrite the result files",
         );
-        ap.refer(&mut is_append)
+        ap.refer(&mut cfg.is_append)
             .add_option(&["-a", "--app"], StoreTrue, "append to result files");
-        ap.refer(&mut nondet).add_option(&["-c", "--check"], StoreTrue, "perform a the non-deterministic check");
-        ap.refer(&mut offset).add_option(&["-i", "--interv"], Store, "select a subset of of trace files");
+        ap.refer(&mut cfg.nondet).add_option(&["-c", "--check"], StoreTrue, "perform a the non-deterministic check");
+        ap.refer(&mut cfg.offset).add_option(&["-i", "--interv"], Store, "select a subset of of trace files");
+        ap.refer(&mut cfg.tol_file).add_option(
+            &["-t", "--tol"],
+            StoreOption,
+            "floating point tolerance rules (scope,target,kind,value)",
+        );
+        ap.refer(&mut cfg.is_call_context).add_option(
+            &["--call-context"],
+            StoreTrue,
+            "report events executed in another function or frame depth as control deviations",
+        );
+        ap.refer(&mut cfg.mask_file).add_option(
+            &["-m", "--mask"],
+            StoreOption,
+            "ignore fields masked as nondeterministic (ID,Field)",
+        );
+        ap.refer(&mut cfg.is_learn).add_option(
+            &["-L", "--learn"],
+            StoreTrue,
+            "learn a nondeterminism mask from the golden runs",
+        );
+        ap.refer(&mut cfg.rules_file).add_option(
+            &["-r", "--rules"],
+            StoreOption,
+            "ignore and normalization rules (TOML)",
+        );
+        ap.refer(&mut cfg.is_pydiff).add_option(
+            &["--pydiff"],
+            StoreTrue,
+            "cross-check the llfi diffs with tracediff.py",
+        );
+        ap.refer(&mut cfg.is_summary).add_option(
+            &["-S", "--summary"],
+            StoreTrue,
+            "summarize the written results (CSV and JSON)",
+        );
+        ap.refer(&mut cfg.is_propagation).add_option(
+            &["-p", "--propagation"],
+            StoreTrue,
+            "track the fault propagation from the injection point",
+        );
+        ap.refer(&mut cfg.is_slice).add_option(
+            &["-x", "--slice"],
+            StoreTrue,
+            "slice backward from the first deviating event",
+        );
+        ap.refer(&mut cfg.slice_event).add_option(
+            &["--slice-event"],
+            StoreOption,
+            "slice backward from the given event index",
+        );
+        ap.refer(&mut cfg.layout_file).add_option(
+            &["--layout"],
+            StoreOption,
+            "type layouts to resolve offsets into field paths (TOML)",
+        );
+        ap.refer(&mut cfg.source_file).add_option(
+            &["--sources"],
+            StoreOption,
+            "source locations of the instruction IDs (ID,File,Line,Function)",
+        );
+        ap.refer(&mut cfg.symbol_file).add_option(
+            &["--symbols"],
+            StoreOption,
+            "names and sizes of global symbols (Symbol,Name,Size,TLS)",
+        );
+        ap.refer(&mut cfg.is_memmap).add_option(
+            &["--memmap"],
+            StoreTrue,
+            "dump the object table of the memory model (CSV and JSON)",
+        );
+        ap.refer(&mut cfg.memmap_event).add_option(
+            &["--memmap-event"],
+            StoreOption,
+            "restrict the memory map and queries to the objects live at the given event index",
+        );
+        ap.refer(&mut cfg.memmap_addr).add_option(
+            &["--memmap-addr"],
+            StoreOption,
+            "print the object that contains the given hex address",
+        );
         ap.parse_args_or_exit();
     }
 
-    start(
-        &*benchmark,
-        is_fi,
-        is_seq,
-        is_llfi_comp,
-        is_overwrite,
-        is_append,
-        max_comp,
-        max_span,
-        nondet,
-        offset
-    );
+    start(&cfg);
 
     /*
     let mut file1 = String::new();
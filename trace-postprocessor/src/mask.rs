@@ -0,0 +1,192 @@
+use comparison::*;
+use error::*;
+use log::*;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use tolerance::*;
+use trace::*;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum MaskField {
+    Value,
+    Pointer,
+    Args,
+    Branch,
+}
+
+// Event fields per instruction ID that already vary between fault-free runs.
+#[derive(Clone, Debug, Default)]
+pub struct Mask {
+    fields: HashMap<String, HashSet<MaskField>>,
+}
+
+impl MaskField {
+    pub fn from(name: &str) -> Result<MaskField, Box<Error>> {
+        match name {
+            "value" => Ok(MaskField::Value),
+            "pointer" => Ok(MaskField::Pointer),
+            "args" => Ok(MaskField::Args),
+            "branch" => Ok(MaskField::Branch),
+            _ => Err(Box::new(SanError::new(&format!("unknown mask field {}", name)))),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MaskField::Value => "value",
+            MaskField::Pointer => "pointer",
+            MaskField::Args => "args",
+            MaskField::Branch => "branch",
+        }
+    }
+}
+
+impl Mask {
+    pub fn new() -> Mask {
+        Mask::default()
+    }
+
+    pub fn insert(&mut self, id: &str, field: MaskField) {
+        self.fields.entry(String::from(id)).or_default().insert(field);
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.values().map(|x| x.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get_entries(&self) -> Vec<(String, MaskField)> {
+        let mut res = Vec::new();
+        for (id, fields) in self.fields.iter() {
+            for f in fields.iter() {
+                res.push((id.clone(), *f));
+            }
+        }
+        res.sort();
+        res
+    }
+
+    // Whether every field two events of the instruction ID differ in is
+    // masked.
+    pub fn covers(&self, id: &str, diff: &EventDiff) -> bool {
+        let masked = match self.fields.get(id) {
+            Some(m) => m,
+            None => return false,
+        };
+        match &diff.fields {
+            Some(f) => !f.is_empty() && f.is_subset(masked),
+            None => false,
+        }
+    }
+
+    // Aligns every trace with the reference trace thread by thread like the
+    // comparison and records the fields that differ. A thread is only learned
+    // up to its first control deviation since the alignment is meaningless
+    // afterwards.
+    pub fn learn(reference: &SymbolicTrace, others: &[SymbolicTrace]) -> Mask {
+        let tol = Tolerance::new();
+        let opts = CompareOptions::default();
+        let mut res = Mask::new();
+        for tr in others.iter() {
+            for th in reference.thread_naming.values() {
+                let events1 = get_projection(reference, th);
+                let events2 = get_projection(tr, th);
+                walk_projection(
+                    reference,
+                    tr,
+                    &events1,
+                    &events2,
+                    &tol,
+                    &opts,
+                    |evt1, evt2, diff| {
+                        if evt1.id != evt2.id {
+                            return false;
+                        }
+                        match &diff.fields {
+                            Some(fields) => {
+                                for f in fields.iter() {
+                                    res.insert(&evt1.id, *f);
+                                }
+                                true
+                            }
+                            None => false,
+                        }
+                    },
+                );
+            }
+            info!("Learned from {}, mask has {} entries", tr.id, res.len());
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    #[test]
+    fn covers_only_masked_fields() {
+        let obj = get_object("a", 0x1000, 8);
+        let other = get_object("b", 0x2000, 8);
+        let tol = Tolerance::new();
+        let opts = CompareOptions::default();
+        let mut mask = Mask::new();
+        mask.insert("1", MaskField::Value);
+        let value = get_event_diff(
+            &get_write("0", 1, &obj, 0, 1),
+            &get_write("0", 1, &obj, 0, 2),
+            &tol,
+            &opts,
+        );
+        assert!(mask.covers("1", &value));
+        assert!(!mask.covers("2", &value));
+        let both = get_event_diff(
+            &get_write("0", 1, &obj, 0, 1),
+            &get_write("0", 1, &other, 0, 2),
+            &tol,
+            &opts,
+        );
+        assert!(!mask.covers("1", &both));
+        let unaligned = get_event_diff(
+            &get_write("0", 1, &obj, 0, 1),
+            &get_read("0", 1, &obj, 0, 1),
+            &tol,
+            &opts,
+        );
+        assert!(!mask.covers("1", &unaligned));
+    }
+
+    #[test]
+    fn learns_up_to_the_first_control_deviation() {
+        let obj = get_object("a", 0x1000, 8);
+        let reference = get_trace(
+            "gr-0",
+            vec![
+                get_write("0", 1, &obj, 0, 1),
+                get_branch("0", 2, 3),
+                get_write("0", 3, &obj, 0, 1),
+                get_write("0", 4, &obj, 0, 1),
+            ],
+        );
+        let other = get_trace(
+            "gr-1",
+            vec![
+                get_write("0", 1, &obj, 0, 2),
+                get_branch("0", 2, 4),
+                get_write("0", 4, &obj, 0, 2),
+            ],
+        );
+        let mask = Mask::learn(&reference, &[other]);
+        assert_eq!(
+            mask.get_entries(),
+            vec![
+                (String::from("1"), MaskField::Value),
+                (String::from("2"), MaskField::Branch)
+            ]
+        );
+    }
+}
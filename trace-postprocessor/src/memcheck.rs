@@ -0,0 +1,256 @@
+use crash::*;
+use object::*;
+use std::sync::Arc;
+use trace::*;
+use utils::*;
+
+// accesses up to this many bytes next to an object (or its size, if it is
+// larger) are attributed to it as out-of-bounds
+const REDZONE: u64 = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    // distance in bytes to the closest live object, positive past its end
+    // (0 is the first byte after it), negative before its start
+    OutOfBounds { object: Arc<Object>, distance: i64 },
+    // inside an object that has already been freed or popped from the stack
+    UseAfterFree { object: Arc<Object>, offset: u64 },
+    Wild,
+}
+
+// Load or store of a trace that couldn't be resolved to a live object.
+#[derive(Clone, Debug)]
+pub struct MemoryViolation {
+    pub thread: String,
+    pub access: MemoryAccess,
+    pub violation: Violation,
+}
+
+impl Violation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Violation::OutOfBounds { .. } => "out-of-bounds",
+            Violation::UseAfterFree { .. } => "use-after-free",
+            Violation::Wild => "wild",
+        }
+    }
+
+    fn get_object(&self) -> Option<&Arc<Object>> {
+        match self {
+            Violation::OutOfBounds { object, .. } | Violation::UseAfterFree { object, .. } => {
+                Some(object)
+            }
+            Violation::Wild => None,
+        }
+    }
+}
+
+fn get_distance(obj: &Object, addr: u64) -> i64 {
+    if addr < obj.address {
+        -((obj.address - addr) as i64)
+    } else {
+        (addr - (obj.address + obj.size)) as i64
+    }
+}
+
+// Identified objects (allocations, stack variables and globals) ordered by
+// their address, the 8 byte objects guessed for unknown pointers aren't
+// indexed. Accesses are checked against the objects starting within a
+// window around the address instead of all objects of the trace.
+pub struct ObjectIndex {
+    objects: Vec<Arc<Object>>,
+    // size of the largest object, at least the redzone
+    window: u64,
+}
+
+impl ObjectIndex {
+    pub fn new(objects: &[Arc<Object>]) -> ObjectIndex {
+        let mut objects = objects
+            .iter()
+            .filter(|x| x.identified && x.size > 0)
+            .cloned()
+            .collect::<Vec<_>>();
+        objects.sort_by_key(|x| x.address);
+        let window = objects
+            .iter()
+            .map(|x| x.size)
+            .max()
+            .unwrap_or(0)
+            .max(REDZONE);
+        ObjectIndex { objects, window }
+    }
+
+    // Objects starting in `from..to`.
+    fn get_range(&self, from: u64, to: u64) -> &[Arc<Object>] {
+        let start = self.objects.partition_point(|x| x.address < from);
+        let end = self.objects.partition_point(|x| x.address < to);
+        &self.objects[start..end]
+    }
+
+    // Object alive at the trace position `pos` that contains the address, if
+    // the access is valid.
+    pub fn get_live_object(&self, addr: u64, pos: usize) -> Option<Arc<Object>> {
+        self.get_range(addr.saturating_sub(self.window), addr.saturating_add(1))
+            .iter()
+            .find(|x| is_inside(x, addr) && pos >= x.construction && pos <= x.destruction)
+            .cloned()
+    }
+
+    // Classifies an address that isn't inside any object alive at the trace
+    // position `pos`.
+    pub fn check_address(&self, addr: u64, pos: usize) -> Violation {
+        // objects up to their size or the redzone away, on both sides
+        let known = self.get_range(
+            addr.saturating_sub(2 * self.window),
+            addr.saturating_add(self.window),
+        );
+        // the most recently destroyed object wins if the address has been reused
+        if let Some(obj) = known
+            .iter()
+            .filter(|x| x.destruction < pos && is_inside(x, addr))
+            .max_by_key(|x| x.destruction)
+        {
+            return Violation::UseAfterFree {
+                object: Arc::clone(obj),
+                offset: addr - obj.address,
+            };
+        }
+        match known
+            .iter()
+            .filter(|x| pos >= x.construction && pos <= x.destruction)
+            .map(|x| (x, get_distance(x, addr)))
+            .filter(|(x, d)| d.unsigned_abs() < x.size.max(REDZONE))
+            .min_by_key(|(_, d)| d.unsigned_abs())
+        {
+            Some((obj, distance)) => Violation::OutOfBounds {
+                object: Arc::clone(obj),
+                distance,
+            },
+            None => Violation::Wild,
+        }
+    }
+}
+
+fn is_inside(obj: &Object, addr: u64) -> bool {
+    addr >= obj.address && addr < obj.address + obj.size
+}
+
+pub fn get_memcheck_header() -> Vec<&'static str> {
+    vec![
+        "Benchmark",
+        "Fault-model",
+        "#",
+        "Timestamp",
+        "Thread",
+        "ID",
+        "Function",
+        "Location",
+        "Access",
+        "Address",
+        "Classification",
+        "Object",
+        "Object-address",
+        "Object-size",
+        "Offset",
+        "Distance",
+    ]
+}
+
+pub fn get_memcheck_records(tr: &SymbolicTrace) -> Vec<Vec<String>> {
+    let inf = tr.get_id_info();
+    tr.violations
+        .iter()
+        .map(|v| {
+            let a = &v.access;
+            let mut res = vec![
+                inf.0.clone(),
+                inf.1.clone(),
+                inf.2.clone(),
+                format!("{}", a.timestamp),
+                v.thread.clone(),
+                format!("{}", a.id),
+                a.func.clone(),
+                tr.get_location(&format!("{}", a.id)),
+                String::from(if a.is_write { "write" } else { "read" }),
+                to_hex(a.address),
+                String::from(v.violation.name()),
+            ];
+            match v.violation.get_object() {
+                Some(obj) => res.extend(vec![
+                    obj.id.clone(),
+                    to_hex(obj.address),
+                    format!("{}", obj.size),
+                ]),
+                None => res.extend(vec![String::new(); 3]),
+            }
+            res.extend(match &v.violation {
+                Violation::OutOfBounds { distance, .. } => {
+                    vec![String::new(), format!("{}", distance)]
+                }
+                Violation::UseAfterFree { offset, .. } => {
+                    vec![format!("{}", offset), String::new()]
+                }
+                Violation::Wild => vec![String::new(); 2],
+            });
+            res
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    fn get_live(id: &str, address: u64, size: u64, lifetime: (usize, usize)) -> Arc<Object> {
+        let mut obj = (*get_object(id, address, size)).clone();
+        obj.construction = lifetime.0;
+        obj.destruction = lifetime.1;
+        Arc::new(obj)
+    }
+
+    fn get_index() -> ObjectIndex {
+        let mut guessed = (*get_live("p", 0x3000, 8, (0, 10))).clone();
+        guessed.identified = false;
+        ObjectIndex::new(&[
+            Arc::new(Object::get_null()),
+            get_live("c", 0x10000, 0x1000, (0, 10)),
+            get_live("a", 0x1000, 16, (0, 10)),
+            get_live("b", 0x2000, 8, (0, 2)),
+            get_live("d", 0x2000, 8, (4, 10)),
+            Arc::new(guessed),
+        ])
+    }
+
+    fn get_id(v: &Violation) -> &str {
+        v.get_object().map_or("", |o| &*o.id)
+    }
+
+    #[test]
+    fn accesses_inside_live_objects_are_valid() {
+        let index = get_index();
+        let live = |addr, pos| index.get_live_object(addr, pos).map(|o| o.id.clone());
+        assert_eq!(live(0x1008, 5), Some(String::from("a")));
+        assert_eq!(live(0x10fff, 5), Some(String::from("c")));
+        assert_eq!(live(0x2004, 5), Some(String::from("d")));
+        assert_eq!(live(0x2004, 3), None);
+        // objects guessed for unknown pointers aren't indexed
+        assert_eq!(live(0x3000, 5), None);
+    }
+
+    #[test]
+    fn invalid_accesses_are_classified() {
+        let index = get_index();
+        let oob = |addr| match index.check_address(addr, 5) {
+            Violation::OutOfBounds { object, distance } => (object.id.clone(), distance),
+            v => panic!("{:?}", v),
+        };
+        assert_eq!(oob(0x1010), (String::from("a"), 0));
+        assert_eq!(oob(0xff8), (String::from("a"), -8));
+        assert_eq!(oob(0x11800), (String::from("c"), 0x800));
+        let uaf = index.check_address(0x2004, 3);
+        assert_eq!((uaf.name(), get_id(&uaf)), ("use-after-free", "b"));
+        assert_eq!(index.check_address(0x50000, 5), Violation::Wild);
+        assert_eq!(index.check_address(0x1080, 5), Violation::Wild);
+    }
+}
@@ -0,0 +1,200 @@
+use object::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use trace::*;
+use utils::*;
+
+// A symbolic object of the memory model with its lifetime in trace
+// positions (`construction`..=`destruction`).
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct MemoryEntry {
+    pub trace: String,
+    pub id: String,
+    pub owner: String,
+    pub kind: &'static str,
+    pub size: u64,
+    pub address: String,
+    pub construction: usize,
+    pub destruction: usize,
+    // allocation instruction, 0 for globals
+    pub inst_id: u32,
+    pub location: String,
+    pub func: String,
+    pub depth: usize,
+}
+
+fn is_live(obj: &Object, pos: usize) -> bool {
+    pos >= obj.construction && pos <= obj.destruction
+}
+
+impl SymbolicTrace {
+    // Trace position of an event, past the events it is the end of the trace.
+    fn get_event_pos(&self, event: usize) -> usize {
+        self.events
+            .get(event)
+            .map_or(self.events.last().map_or(0, |e| e.inst + 1), |e| e.inst)
+    }
+
+    // Object that contains the address at event `event`, resolved like the
+    // accesses of the symbolic events.
+    pub fn get_object_at(&self, address: u64, event: usize) -> Option<Arc<Object>> {
+        let pos = self.get_event_pos(event);
+        self.get_object(address, pos, &mut HashMap::new())
+    }
+
+    // Objects live at event `event`, ordered by address.
+    pub fn get_live_objects(&self, event: usize) -> Vec<Arc<Object>> {
+        let pos = self.get_event_pos(event);
+        let mut res = self
+            .objects
+            .iter()
+            .filter(|o| is_live(o, pos))
+            .cloned()
+            .collect::<Vec<_>>();
+        res.sort_by_key(|o| o.address);
+        res
+    }
+
+    // Object table of the trace, restricted to the objects live at `event`
+    // if it is given.
+    pub fn get_memory_map(&self, event: Option<usize>) -> Vec<MemoryEntry> {
+        let heap = self
+            .heap
+            .get_blocks()
+            .iter()
+            .map(|b| &*b.object.id)
+            .collect::<HashSet<_>>();
+        let objects = match event {
+            Some(e) => self.get_live_objects(e),
+            None => self.objects.clone(),
+        };
+        objects
+            .iter()
+            .map(|o| MemoryEntry {
+                trace: self.id.clone(),
+                id: o.id.clone(),
+                owner: o.owner.clone(),
+                kind: get_kind(o, &heap),
+                size: o.size,
+                address: to_hex(o.address),
+                construction: o.construction,
+                destruction: o.destruction,
+                inst_id: o.inst_id,
+                location: self.get_location(&format!("{}", o.inst_id)),
+                func: o.func.clone(),
+                depth: o.depth,
+            })
+            .collect()
+    }
+}
+
+fn get_kind(obj: &Object, heap: &HashSet<&str>) -> &'static str {
+    if obj.id == "NULL" {
+        "null"
+    } else if !obj.identified {
+        // created for a pointer to an unknown address
+        "pointer"
+    } else if obj.is_stack {
+        "stack"
+    } else if heap.contains(&*obj.id) {
+        "heap"
+    } else if obj.id.contains('@') {
+        "tls"
+    } else {
+        "global"
+    }
+}
+
+impl MemoryEntry {
+    pub fn get_record(&self) -> Vec<String> {
+        vec![
+            self.trace.clone(),
+            self.id.clone(),
+            self.owner.clone(),
+            String::from(self.kind),
+            format!("{}", self.size),
+            self.address.clone(),
+            format!("{}", self.construction),
+            format!("{}", self.destruction),
+            format!("{}", self.inst_id),
+            self.location.clone(),
+            self.func.clone(),
+            format!("{}", self.depth),
+        ]
+    }
+}
+
+pub fn get_memmap_header() -> Vec<&'static str> {
+    vec![
+        "Trace",
+        "Object",
+        "Owner",
+        "Kind",
+        "Size",
+        "Address",
+        "Construction",
+        "Destruction",
+        "ID",
+        "Location",
+        "Function",
+        "Depth",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    fn get_memmap_trace() -> SymbolicTrace {
+        let a = get_object("a", 0x1000, 16);
+        let mut tr = get_trace(
+            "bench_trace.0-0",
+            vec![
+                get_write("0", 1, &a, 0, 1),
+                get_write("0", 2, &a, 8, 1),
+                get_write("0", 3, &a, 8, 1),
+            ],
+        );
+        let mut stack = (*get_object("main::x", 0x7000, 8)).clone();
+        stack.is_stack = true;
+        stack.construction = 1;
+        let tls = get_object("tlv@1", 0x3000, 4);
+        tr.objects = vec![Arc::new(Object::get_null()), Arc::new(stack), tls, a];
+        tr
+    }
+
+    #[test]
+    fn objects_are_resolved_at_an_event() {
+        let tr = get_memmap_trace();
+        let obj = tr.get_object_at(0x1008, 0).unwrap();
+        assert_eq!(obj.id, "a");
+        assert!(tr.get_object_at(0x1010, 0).is_none());
+        assert!(tr.get_object_at(0x7000, 0).is_none());
+        assert_eq!(tr.get_object_at(0x7004, 1).unwrap().id, "main::x");
+        // past the end of the trace
+        assert!(tr.get_object_at(0x1008, 10).is_none());
+        let live = tr.get_live_objects(1);
+        let ids = live.iter().map(|o| &*o.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["NULL", "a", "tlv@1", "main::x"]);
+    }
+
+    #[test]
+    fn memory_map_has_the_kinds_of_the_objects() {
+        let tr = get_memmap_trace();
+        let entries = tr.get_memory_map(None);
+        let kinds = entries.iter().map(|e| (&*e.id, e.kind)).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("NULL", "null"),
+                ("main::x", "stack"),
+                ("tlv@1", "tls"),
+                ("a", "global"),
+            ]
+        );
+        assert_eq!(entries[3].address, to_hex(0x1000));
+        assert_eq!(entries[0].get_record().len(), get_memmap_header().len());
+        assert_eq!(tr.get_memory_map(Some(0)).len(), 3);
+    }
+}
@@ -13,8 +13,12 @@ use petgraph::Direction;
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 use std::iter::FromIterator;
+use utils::get_long;
 
-pub fn is_deterministic(_tr: &mut SymbolicTrace, benchmark: &str) -> (bool, u64, u64, u64, Duration) {
+pub fn is_deterministic(
+    _tr: &mut SymbolicTrace,
+    benchmark: &str,
+) -> (bool, u64, u64, u64, Duration, Vec<Vec<String>>) {
     //check_ev(_tr);
     //panic!();
     let mut formula = Formula::new(benchmark);
@@ -43,7 +47,55 @@ pub fn is_deterministic(_tr: &mut SymbolicTrace, benchmark: &str) -> (bool, u64,
     let result = !formula.check_nondet(&rw, true);
     let end = start.elapsed();
 
-    (result, num_deps, g_evs  as u64, g_objs as u64, end)
+    let races = get_race_records(_tr, &rw);
+    (result, num_deps, g_evs  as u64, g_objs as u64, end, races)
+}
+
+// Reads and the writes of other threads to the same location they depend
+// on, i.e. the candidate races of the check, with their source locations.
+fn get_race_records(
+    trace: &SymbolicTrace,
+    deps: &HashMap<&Event, HashSet<&Event>>,
+) -> Vec<Vec<String>> {
+    let mut pairs = deps
+        .iter()
+        .flat_map(|(r, ws)| ws.iter().map(move |w| (*r, *w)))
+        .collect::<Vec<_>>();
+    pairs.sort_by_key(|(r, w)| (get_long(&r.timestamp), get_long(&w.timestamp)));
+    pairs
+        .into_iter()
+        .map(|(r, w)| {
+            let (object, offset) = match &r.data {
+                EventType::Read { object, offset, .. } => {
+                    (object.id.clone(), format!("{}", offset))
+                }
+                _ => (String::new(), String::new()),
+            };
+            vec![
+                object,
+                offset,
+                r.thread.clone(),
+                r.id.clone(),
+                trace.get_location(&r.id),
+                w.thread.clone(),
+                w.id.clone(),
+                trace.get_location(&w.id),
+            ]
+        })
+        .collect()
+}
+
+pub fn get_race_header() -> Vec<&'static str> {
+    vec![
+        "Object",
+        "Offset",
+        "Read-thread",
+        "Read-ID",
+        "Read-location",
+        "Write-thread",
+        "Write-ID",
+        "Write-location",
+    ]
 }
 
 
@@ -545,3 +597,47 @@ fn get_write_set(_ev: &Event) -> Vec<(Arc<Object>, Option<u64>)> {
 
     return res;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use source::*;
+    use testutils::*;
+
+    #[test]
+    fn race_records_have_source_locations() {
+        let a = get_object("a", 0x1000, 8);
+        let mut tr = get_trace(
+            "bench_trace.0-0",
+            vec![
+                get_write("1", 2, &a, 0, 1),
+                get_write("2", 3, &a, 0, 2),
+                get_read("0", 1, &a, 0, 2),
+            ],
+        );
+        let mut sources = SourceMap::new();
+        sources.insert(
+            1,
+            SourceLocation {
+                file: String::from("main.c"),
+                line: 7,
+                function: String::from("main"),
+            },
+        );
+        tr.sources = Arc::new(sources);
+        let mut deps = HashMap::new();
+        deps.insert(
+            &tr.events[2],
+            vec![&tr.events[1], &tr.events[0]].into_iter().collect(),
+        );
+        let records = get_race_records(&tr, &deps);
+        assert_eq!(
+            records,
+            vec![
+                vec!["a", "0", "0", "1", "main.c:7", "1", "2", ""],
+                vec!["a", "0", "0", "1", "main.c:7", "2", "3", ""],
+            ]
+        );
+        assert_eq!(records[0].len(), get_race_header().len());
+    }
+}
@@ -1,6 +1,11 @@
 use csv::*;
 use instruction::*;
+use std::collections::HashMap;
 use utils::*;
+
+// number of innermost frames that make up the allocation context
+const CONTEXT_DEPTH: usize = 3;
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Object {
     pub id: String,
@@ -13,6 +18,9 @@ pub struct Object {
     pub identified: bool,
     pub hex_addr: String,
     pub inst_id: u32,
+    // function and call depth of the frame of stack objects
+    pub func: String,
+    pub depth: usize,
 }
 impl Object {
     pub fn from_global(record: &StringRecord) -> Object {
@@ -20,10 +28,15 @@ impl Object {
         let tmp: Vec<&str> = val.split('-').collect();
 
         let address = get_hex(tmp[2]);
+        let mut size: u64 = record.get(2).unwrap().to_string().parse().unwrap();
+        // the size is per element for arrays with an element count
+        if let Some(n) = record.get(3).and_then(|x| x.trim().parse::<u64>().ok()) {
+            size *= n;
+        }
         Object {
             id: record.get(0).unwrap().to_string(),
             is_stack: false,
-            size: record.get(2).unwrap().to_string().parse().unwrap(),
+            size,
             address: address,
             owner: String::from("0"),
             construction: 0,
@@ -31,6 +44,8 @@ impl Object {
             identified: true,
             hex_addr: String::from(tmp[2]),
             inst_id: 0,
+            func: String::new(),
+            depth: 0,
         }
     }
     pub fn get_null() -> Object {
@@ -45,19 +60,19 @@ impl Object {
             identified: true,
             hex_addr: String::from("00000000000"),
             inst_id: 0,
+            func: String::new(),
+            depth: 0,
         }
     }
     pub fn from(
         inst: &Instruction,
-        idx: u32,
+        name: &str,
         pos: usize,
         identified: bool,
         owner: &str,
     ) -> Vec<Object> {
         let mut _res = Vec::new();
-        let i: &str = &idx.to_string()[..];
-        let mut tmp: String = "v".to_string();
-        tmp.push_str(i);
+        let mut tmp = String::from(name);
         let mut address = get_hex(&*inst.value.value);
         let mut size = inst.value.type_size;
         if (((inst.is_undeclared_call() && !inst.is_allocation())
@@ -68,9 +83,7 @@ impl Object {
             //warn!("Values: {:#?}", _vals);
             let mut j = 0;
             for v in _vals.iter() {
-                let i: &str = &(idx + j).to_string()[..];
-                tmp = "v".to_string();
-                tmp.push_str(i);
+                tmp = format!("{}.{}", name, j);
                 //                warn!("CHECK: {:#?}", inst);
                 address = get_hex(&*v.value);
 
@@ -86,12 +99,16 @@ impl Object {
                     identified: identified,
                     hex_addr: String::from(&*v.value),
                     inst_id: inst.instruction_id,
+                    func: String::new(),
+                    depth: 0,
                 });
                 j += 1;
             }
             return _res;
         }
-        if inst.is_malloc() || inst.is_znam() {
+        if inst.is_realloc() {
+            size = get_hex(&*inst.operands[1].value);
+        } else if inst.is_heap_allocation() {
             size = get_hex(&*inst.operands[0].value);
         } else if inst.is_alloca() {
             size = get_hex(&*inst.operands[1].value) * get_hex(&*inst.operands[0].value);
@@ -114,6 +131,8 @@ impl Object {
             identified: identified,
             hex_addr: String::from(&*inst.value.value),
             inst_id: inst.instruction_id,
+            func: String::new(),
+            depth: 0,
         };
 
         _res.push(obj);
@@ -124,3 +143,49 @@ impl Object {
         self.destruction = pos;
     }
 }
+
+// Names objects after their allocation site instead of a running counter,
+// so that an additional allocation doesn't shift the names of all following
+// objects: `<thread>:<context>:<instruction ID>#<occurrence>`, the context
+// being the innermost functions of the call stack.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectNaming {
+    occurrences: HashMap<String, u32>,
+}
+
+impl ObjectNaming {
+    pub fn new() -> ObjectNaming {
+        ObjectNaming::default()
+    }
+
+    pub fn get_name(&mut self, owner: &str, frames: &[String], inst_id: u32) -> String {
+        let context = frames[frames.len().saturating_sub(CONTEXT_DEPTH)..].join(">");
+        let site = format!("{}:{}:{}", owner, context, inst_id);
+        let occ = self.occurrences.entry(site.clone()).or_insert(0);
+        *occ += 1;
+        format!("{}#{}", site, occ)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_frames(frames: &[&str]) -> Vec<String> {
+        frames.iter().map(|f| String::from(*f)).collect()
+    }
+
+    #[test]
+    fn objects_are_named_after_their_allocation_site() {
+        let mut naming = ObjectNaming::new();
+        let frames = get_frames(&["main", "run", "solve", "alloc"]);
+        assert_eq!(naming.get_name("0", &frames, 7), "0:run>solve>alloc:7#1");
+        assert_eq!(naming.get_name("0", &frames, 7), "0:run>solve>alloc:7#2");
+        // the context is limited to the innermost frames, the occurrences
+        // are counted per thread, context and instruction
+        assert_eq!(naming.get_name("1", &frames, 7), "1:run>solve>alloc:7#1");
+        assert_eq!(naming.get_name("0", &frames, 8), "0:run>solve>alloc:8#1");
+        assert_eq!(naming.get_name("0", &frames[..1], 7), "0:main:7#1");
+        assert_eq!(naming.get_name("0", &[], 7), "0::7#1");
+    }
+}
@@ -0,0 +1,252 @@
+use fileio::*;
+use std::collections::BTreeMap;
+use trace::*;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum Outcome {
+    NoActivation,
+    Benign,
+    MaskedInTrace,
+    Sdc,
+    Detected,
+    Crash,
+    Hang,
+    Unknown,
+}
+
+pub const OUTCOMES: [Outcome; 8] = [
+    Outcome::NoActivation,
+    Outcome::Benign,
+    Outcome::MaskedInTrace,
+    Outcome::Sdc,
+    Outcome::Detected,
+    Outcome::Crash,
+    Outcome::Hang,
+    Outcome::Unknown,
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExitStatus {
+    Exit(i64),
+    Signal(i64),
+    Timeout,
+    Unknown,
+}
+
+// Interprets the content of the _retc file: shell style (128 + n) and
+// negative (-n) exit codes denote signals.
+pub fn get_exit_status(ret_code: &str) -> ExitStatus {
+    let code = ret_code.trim().to_lowercase();
+    if code.contains("timeout") || code.contains("hang") {
+        return ExitStatus::Timeout;
+    }
+    match code.parse::<i64>() {
+        Ok(c) if c < 0 => ExitStatus::Signal(-c),
+        Ok(c) if c > 128 && c < 160 => ExitStatus::Signal(c - 128),
+        Ok(c) => ExitStatus::Exit(c),
+        Err(_) => ExitStatus::Unknown,
+    }
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::NoActivation => "no-activation",
+            Outcome::Benign => "benign",
+            Outcome::MaskedInTrace => "masked-in-trace",
+            Outcome::Sdc => "sdc",
+            Outcome::Detected => "detected",
+            Outcome::Crash => "crash",
+            Outcome::Hang => "hang",
+            Outcome::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Outcome> {
+        OUTCOMES.iter().find(|x| x.name() == name).cloned()
+    }
+
+    // Outcome of a faulty run with respect to its golden run and the result
+    // of the trace comparison.
+    pub fn classify(golden: &SymbolicTrace, faulty: &SymbolicTrace, deviation: &str) -> Outcome {
+        if !faulty.is_injected() {
+            return Outcome::NoActivation;
+        }
+        match get_exit_status(&faulty.ret_code) {
+            ExitStatus::Signal(_) => return Outcome::Crash,
+            ExitStatus::Timeout => return Outcome::Hang,
+            // a truncated trace without return code has been killed
+            ExitStatus::Unknown if faulty.truncated => return Outcome::Hang,
+            status => {
+                if status != get_exit_status(&golden.ret_code) {
+                    return Outcome::Detected;
+                }
+            }
+        }
+        if faulty.is_output_deviating(golden) {
+            return Outcome::Sdc;
+        }
+        // the traces couldn't be compared: an empty golden projection or a
+        // failed comparison of the LLFI traces
+        if deviation == "empty-dev" || deviation == "error" {
+            return Outcome::Unknown;
+        }
+        if deviation != "no-dev" && deviation != "within-tol" {
+            return Outcome::MaskedInTrace;
+        }
+        Outcome::Benign
+    }
+}
+
+pub fn get_outcome_header() -> Vec<&'static str> {
+    let mut res = vec!["Benchmark", "Fault-model"];
+    res.extend(OUTCOMES.iter().map(|x| x.name()));
+    res.push("Total");
+    res
+}
+
+// Number of runs per outcome class and fault model.
+pub fn summarize_outcomes(results: &[Vec<String>]) -> Vec<Vec<String>> {
+    let mut counts: BTreeMap<(String, String), BTreeMap<Outcome, u64>> = BTreeMap::new();
+    for rec in results.iter() {
+        let key = (
+            rec[get_column("Benchmark")].clone(),
+            rec[get_column("Fault-model")].clone(),
+        );
+        if let Some(o) = Outcome::from_name(&rec[get_column("Outcome")]) {
+            *counts.entry(key).or_default().entry(o).or_insert(0) += 1;
+        }
+    }
+    let mut res = Vec::new();
+    for ((bench, fm), cnt) in counts.iter() {
+        let mut row = vec![bench.clone(), fm.clone()];
+        for o in OUTCOMES.iter() {
+            row.push(format!("{}", cnt.get(o).unwrap_or(&0)));
+        }
+        row.push(format!("{}", cnt.values().sum::<u64>()));
+        res.push(row);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_run(ret_code: &str, output_hash: &str, injected: bool) -> SymbolicTrace {
+        let mut tr = SymbolicTrace::new("bench_trace.1-0");
+        tr.ret_code = String::from(ret_code);
+        tr.output_hash = String::from(output_hash);
+        if injected {
+            tr.injection.push((10, 7));
+        }
+        tr
+    }
+
+    #[test]
+    fn exit_status_of_return_codes() {
+        assert_eq!(get_exit_status("0"), ExitStatus::Exit(0));
+        assert_eq!(get_exit_status("1\n"), ExitStatus::Exit(1));
+        assert_eq!(get_exit_status("139"), ExitStatus::Signal(11));
+        assert_eq!(get_exit_status("-6"), ExitStatus::Signal(6));
+        assert_eq!(get_exit_status("TIMEOUT"), ExitStatus::Timeout);
+        assert_eq!(get_exit_status(""), ExitStatus::Unknown);
+    }
+
+    #[test]
+    fn runs_are_classified() {
+        let golden = get_run("0", "a", false);
+        let classify = |tr: &SymbolicTrace, dev: &str| Outcome::classify(&golden, tr, dev);
+        assert_eq!(
+            classify(&get_run("0", "a", false), "data-dev"),
+            Outcome::NoActivation
+        );
+        assert_eq!(
+            classify(&get_run("0", "a", true), "no-dev"),
+            Outcome::Benign
+        );
+        assert_eq!(
+            classify(&get_run("0", "a", true), "within-tol"),
+            Outcome::Benign
+        );
+        assert_eq!(
+            classify(&get_run("0", "a", true), "data-dev"),
+            Outcome::MaskedInTrace
+        );
+        assert_eq!(classify(&get_run("0", "b", true), "data-dev"), Outcome::Sdc);
+        assert_eq!(
+            classify(&get_run("1", "b", true), "data-dev"),
+            Outcome::Detected
+        );
+        assert_eq!(
+            classify(&get_run("139", "b", true), "data-dev"),
+            Outcome::Crash
+        );
+        assert_eq!(
+            classify(&get_run("timeout", "b", true), "data-dev"),
+            Outcome::Hang
+        );
+    }
+
+    #[test]
+    fn truncated_runs_are_classified_by_their_exit_status() {
+        let golden = get_run("0", "a", false);
+        let truncated = |ret_code: &str, output_hash: &str| {
+            let mut tr = get_run(ret_code, output_hash, true);
+            tr.truncated = true;
+            Outcome::classify(&golden, &tr, "data-dev")
+        };
+        assert_eq!(truncated("", ""), Outcome::Hang);
+        assert_eq!(truncated("timeout", ""), Outcome::Hang);
+        assert_eq!(truncated("139", ""), Outcome::Crash);
+        assert_eq!(truncated("1", ""), Outcome::Detected);
+        // the output of a run without output hash is unknown
+        assert_eq!(truncated("0", ""), Outcome::MaskedInTrace);
+        assert_eq!(truncated("0", "b"), Outcome::Sdc);
+    }
+
+    #[test]
+    fn empty_projections_are_not_masked() {
+        let golden = get_run("0", "a", false);
+        let classify = |tr: &SymbolicTrace| Outcome::classify(&golden, tr, "empty-dev");
+        assert_eq!(classify(&get_run("0", "a", true)), Outcome::Unknown);
+        assert_eq!(classify(&get_run("0", "b", true)), Outcome::Sdc);
+        assert_eq!(classify(&get_run("139", "a", true)), Outcome::Crash);
+    }
+
+    #[test]
+    fn failed_comparisons_are_not_masked() {
+        let golden = get_run("0", "a", false);
+        let classify = |tr: &SymbolicTrace| Outcome::classify(&golden, tr, "error");
+        assert_eq!(classify(&get_run("0", "a", true)), Outcome::Unknown);
+        assert_eq!(classify(&get_run("timeout", "a", true)), Outcome::Hang);
+        assert_eq!(classify(&get_run("0", "a", false)), Outcome::NoActivation);
+    }
+
+    #[test]
+    fn outcomes_are_counted_per_fault_model() {
+        let get_record = |fm: &str, outcome: Outcome| {
+            let mut rec = vec![String::new(); RESULT_COLUMNS.len()];
+            rec[get_column("Benchmark")] = String::from("bench");
+            rec[get_column("Fault-model")] = String::from(fm);
+            rec[get_column("Outcome")] = String::from(outcome.name());
+            rec
+        };
+        let results = vec![
+            get_record("1", Outcome::Sdc),
+            get_record("1", Outcome::Sdc),
+            get_record("1", Outcome::Crash),
+            get_record("2", Outcome::Benign),
+        ];
+        let res = summarize_outcomes(&results);
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            res[0],
+            vec!["bench", "1", "0", "0", "0", "2", "0", "1", "0", "0", "3"]
+        );
+        assert_eq!(
+            res[1],
+            vec!["bench", "2", "0", "1", "0", "0", "0", "0", "0", "0", "1"]
+        );
+    }
+}
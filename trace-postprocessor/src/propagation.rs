@@ -0,0 +1,362 @@
+use comparison::*;
+use mask::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tolerance::*;
+use trace::*;
+use utils::*;
+
+const OUTPUT_FUNCTIONS: [&str; 10] = [
+    "write", "printf", "fprintf", "puts", "fputs", "fwrite", "putchar", "putc", "fputc", "vfprintf",
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dependency {
+    // the event of the injected instruction
+    Injection,
+    // read of a location written with a corrupted value
+    Memory,
+    // value derived within a thread that has consumed a corrupted value
+    Data,
+    // tainted thread executes other instructions than in the golden run
+    Control,
+    // deviation the corrupted values haven't reached, e.g. nondeterminism
+    Unknown,
+}
+
+impl Dependency {
+    pub fn is_fault(self) -> bool {
+        self != Dependency::Unknown
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PropagationStep {
+    pub event: usize,
+    pub id: String,
+    // number of events executed since the injection
+    pub distance: usize,
+    pub dependency: Dependency,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Propagation {
+    pub injection: Option<usize>,
+    pub steps: Vec<PropagationStep>,
+    pub objects: BTreeSet<String>,
+    pub threads: BTreeSet<String>,
+    pub reached_output: bool,
+}
+
+// Event at which the first fault was injected, i.e. the first event of the
+// injected instruction after the injection timestamp.
+fn get_injection_event(tr: &SymbolicTrace) -> Option<usize> {
+    let (ts, inst) = *tr.injection.iter().min()?;
+    let mut after = tr
+        .events
+        .iter()
+        .enumerate()
+        .filter(|(_, ev)| get_long(&ev.timestamp) >= ts);
+    let first = after.clone().next().map(|(i, _)| i);
+    after
+        .find(|(_, ev)| ev.id == inst.to_string())
+        .map(|(i, _)| i)
+        .or(first)
+}
+
+fn get_location(ev: &Event) -> Option<(String, u64)> {
+    match &ev.data {
+        EventType::Read { object, offset, .. } | EventType::Write { object, offset, .. } => {
+            Some((object.id.clone(), *offset))
+        }
+        _ => None,
+    }
+}
+
+fn get_objects(ev: &Event) -> Vec<String> {
+    let mut res = Vec::new();
+    let add_data = |data: &EventData, res: &mut Vec<String>| {
+        if let EventData::Pointer { target, .. } = data {
+            res.push(target.id.clone());
+        }
+    };
+    match &ev.data {
+        EventType::Read { value, object, .. } | EventType::Write { value, object, .. } => {
+            res.push(object.id.clone());
+            add_data(value, &mut res);
+        }
+        EventType::Lock { mutex } | EventType::Unlock { mutex } => res.push(mutex.id.clone()),
+        EventType::Call { value, args, .. } => {
+            add_data(value, &mut res);
+            for a in args.iter() {
+                add_data(a, &mut res);
+            }
+        }
+        _ => {}
+    }
+    res
+}
+
+pub fn is_output_call(ev: &Event) -> bool {
+    match &ev.data {
+        EventType::Call { name, .. } => OUTPUT_FUNCTIONS.contains(&&**name),
+        _ => false,
+    }
+}
+
+// Follows the fault from the injected event through the faulty trace. Each
+// thread is aligned with its golden projection. The fault is propagated
+// only along tainted values: the injected thread is tainted, locations
+// written with a deviating value by a tainted thread are tainted and a
+// thread becomes tainted when it reads a corrupted value from a tainted
+// location. Deviating events of tainted threads are attributed to the
+// memory dependency, the def-use chain within the thread or a control
+// deviation; deviations the fault can't have caused are labelled unknown.
+pub fn track_propagation(
+    golden: &SymbolicTrace,
+    faulty: &SymbolicTrace,
+    tol: &Tolerance,
+    opts: &CompareOptions,
+    mask: &Mask,
+) -> Propagation {
+    let mut res = Propagation {
+        reached_output: faulty.is_output_deviating(golden),
+        ..Propagation::default()
+    };
+    let start = match get_injection_event(faulty) {
+        Some(s) => s,
+        None => return res,
+    };
+    res.injection = Some(start);
+    let mut projections = HashMap::new();
+    for th in golden.thread_naming.values() {
+        projections.insert(th.clone(), get_projection(golden, th));
+    }
+    let mut positions: HashMap<&String, usize> = HashMap::new();
+    // dependency of all events of a thread after its control deviation
+    let mut diverged: HashMap<&String, Dependency> = HashMap::new();
+    let mut tainted_threads: HashSet<&String> = HashSet::new();
+    let mut tainted: HashSet<(String, u64)> = HashSet::new();
+    for (i, ev) in faulty.events.iter().enumerate() {
+        let pos = positions.entry(&ev.thread).or_insert(0);
+        let golden_ev = projections.get(&ev.thread).and_then(|p| p.get(*pos));
+        *pos += 1;
+        if i < start {
+            continue;
+        }
+        if i == start {
+            tainted_threads.insert(&ev.thread);
+        }
+        let is_tainted = tainted_threads.contains(&ev.thread);
+        let control = if is_tainted {
+            Dependency::Control
+        } else {
+            Dependency::Unknown
+        };
+        let mut dependency = None;
+        if let Some(d) = diverged.get(&ev.thread) {
+            dependency = Some(*d);
+        } else {
+            match golden_ev {
+                Some(g) if g.id == ev.id && g.depth == ev.depth => {
+                    let diff = get_event_diff(g, ev, tol, opts);
+                    if diff.is_deviation() && !mask.covers(&g.id, &diff) {
+                        dependency = Some(if i == start {
+                            Dependency::Injection
+                        } else if let EventType::Branch { .. } = ev.data {
+                            diverged.insert(&ev.thread, control);
+                            control
+                        } else if get_location(ev).is_some_and(|l| tainted.contains(&l))
+                            && matches!(ev.data, EventType::Read { .. })
+                        {
+                            tainted_threads.insert(&ev.thread);
+                            Dependency::Memory
+                        } else if is_tainted {
+                            Dependency::Data
+                        } else {
+                            Dependency::Unknown
+                        });
+                    }
+                }
+                _ => {
+                    diverged.insert(&ev.thread, control);
+                    dependency = Some(control);
+                }
+            }
+        }
+        if let EventType::Write { .. } = ev.data {
+            let loc = get_location(ev).unwrap();
+            if dependency.is_some_and(|d| d.is_fault()) {
+                tainted.insert(loc);
+            } else {
+                tainted.remove(&loc);
+            }
+        }
+        if let Some(d) = dependency {
+            if d.is_fault() {
+                res.objects.extend(get_objects(ev));
+                res.threads.insert(ev.thread.clone());
+                res.reached_output |= is_output_call(ev);
+            }
+            res.steps.push(PropagationStep {
+                event: i,
+                id: ev.id.clone(),
+                distance: i - start,
+                dependency: d,
+            });
+        }
+    }
+    res
+}
+
+impl Propagation {
+    // Distance to the last event the fault has propagated to.
+    pub fn get_distance(&self) -> usize {
+        self.steps
+            .iter()
+            .rev()
+            .find(|s| s.dependency.is_fault())
+            .map_or(0, |s| s.distance)
+    }
+
+    pub fn count(&self, dependency: Dependency) -> usize {
+        self.steps.iter().filter(|s| s.dependency == dependency).count()
+    }
+}
+
+pub fn get_propagation_header() -> Vec<&'static str> {
+    vec![
+        "Benchmark",
+        "Fault-model",
+        "#",
+        "Injection",
+        "First-deviation",
+        "Deviations",
+        "Memory",
+        "Data",
+        "Control",
+        "Unknown",
+        "Distance",
+        "Objects",
+        "Threads",
+        "Reached-output",
+    ]
+}
+
+pub fn get_propagation_record(faulty: &SymbolicTrace, prop: &Propagation) -> Vec<String> {
+    let inf = faulty.get_id_info();
+    let injection = prop
+        .injection
+        .map_or(String::new(), |i| faulty.events[i].id.clone());
+    vec![
+        inf.0,
+        inf.1,
+        inf.2,
+        injection,
+        prop.steps.first().map_or(String::new(), |s| s.id.clone()),
+        format!("{}", prop.steps.len()),
+        format!("{}", prop.count(Dependency::Memory)),
+        format!("{}", prop.count(Dependency::Data)),
+        format!("{}", prop.count(Dependency::Control)),
+        format!("{}", prop.count(Dependency::Unknown)),
+        format!("{}", prop.get_distance()),
+        prop.objects.iter().cloned().collect::<Vec<_>>().join(";"),
+        prop.threads.iter().cloned().collect::<Vec<_>>().join(";"),
+        format!("{}", prop.reached_output),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    #[test]
+    fn fault_propagates_only_along_tainted_values() {
+        let x = get_object("x", 0x1000, 8);
+        let y = get_object("y", 0x2000, 8);
+        let z = get_object("z", 0x3000, 8);
+        let golden = get_trace(
+            "bench_trace.0-0",
+            vec![
+                get_write("0", 1, &x, 0, 4),
+                get_read("1", 2, &x, 0, 4),
+                get_write("1", 3, &y, 0, 5),
+                get_write("2", 4, &z, 0, 8),
+                get_read("3", 5, &z, 0, 8),
+                get_branch("2", 6, 7),
+                get_read("2", 7, &x, 0, 4),
+            ],
+        );
+        let mut faulty = get_trace(
+            "bench_trace.1-0",
+            vec![
+                get_write("0", 1, &x, 0, 5),
+                get_read("1", 2, &x, 0, 5),
+                get_write("1", 3, &y, 0, 6),
+                // deviations of threads the corrupted value hasn't reached
+                get_write("2", 4, &z, 0, 7),
+                get_read("3", 5, &z, 0, 7),
+                get_branch("2", 6, 8),
+                get_read("2", 7, &x, 0, 5),
+            ],
+        );
+        faulty.injection.push((0, 1));
+        let prop = track_propagation(
+            &golden,
+            &faulty,
+            &Tolerance::new(),
+            &CompareOptions::default(),
+            &Mask::new(),
+        );
+        assert_eq!(prop.injection, Some(0));
+        let deps = prop.steps.iter().map(|s| s.dependency).collect::<Vec<_>>();
+        assert_eq!(
+            deps,
+            vec![
+                Dependency::Injection,
+                Dependency::Memory,
+                Dependency::Data,
+                Dependency::Unknown,
+                Dependency::Unknown,
+                Dependency::Unknown,
+                Dependency::Unknown,
+            ]
+        );
+        assert_eq!(prop.threads.iter().collect::<Vec<_>>(), vec!["0", "1"]);
+        assert_eq!(prop.objects.iter().collect::<Vec<_>>(), vec!["x", "y"]);
+        assert_eq!(prop.get_distance(), 2);
+        assert!(!prop.reached_output);
+    }
+
+    #[test]
+    fn masked_writes_untaint_locations() {
+        let x = get_object("x", 0x1000, 8);
+        let golden = get_trace(
+            "bench_trace.0-0",
+            vec![
+                get_write("0", 1, &x, 0, 4),
+                get_write("0", 2, &x, 0, 1),
+                get_read("1", 3, &x, 0, 1),
+            ],
+        );
+        let mut faulty = get_trace(
+            "bench_trace.1-0",
+            vec![
+                get_write("0", 1, &x, 0, 5),
+                get_write("0", 2, &x, 0, 1),
+                get_read("1", 3, &x, 0, 2),
+            ],
+        );
+        faulty.injection.push((0, 1));
+        let prop = track_propagation(
+            &golden,
+            &faulty,
+            &Tolerance::new(),
+            &CompareOptions::default(),
+            &Mask::new(),
+        );
+        assert_eq!(prop.steps.len(), 2);
+        assert_eq!(prop.steps[1].dependency, Dependency::Unknown);
+        assert_eq!(prop.count(Dependency::Memory), 0);
+    }
+}
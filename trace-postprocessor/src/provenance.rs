@@ -0,0 +1,96 @@
+use instruction::*;
+use object::*;
+use rules::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use trace::*;
+use utils::*;
+use value::*;
+
+// Address a pointer-sized integer operand may point to once the tag bits are
+// cleared, the null page is never considered. The provenance isn't tracked
+// through casts or arithmetic: only the value of the operand is looked at, so
+// an integer is attributed to whichever live object it points into, which
+// needn't be the object it has been derived from.
+pub fn get_provenance_address(rules: &Rules, val: &Value) -> Option<u64> {
+    if !rules.provenance || val.type_id != INTEGER_TYPE || val.type_size != 8 {
+        return None;
+    }
+    let bits = u64::from_str_radix(&val.value, 16).ok()?;
+    let addr = bits & !((1u64 << rules.tag_bits.min(63)) - 1);
+    if addr < 4096 {
+        return None;
+    }
+    Some(addr)
+}
+
+impl SymbolicTrace {
+    // Pointer data of a pointer-sized integer operand that points into a live
+    // object, the offset keeps the tag bits.
+    pub fn get_provenance(
+        &self,
+        val: &Value,
+        pos: usize,
+        cache: &mut HashMap<u64, Vec<Arc<Object>>>,
+        rules: &Rules,
+    ) -> Option<EventData> {
+        let addr = get_provenance_address(rules, val)?;
+        let obj = self.get_object(addr, pos, cache)?;
+        let offset = get_hex(&val.value) - obj.address;
+        Some(EventData::Pointer {
+            target: obj,
+            offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+    use toml;
+
+    fn val(s: &str) -> Value {
+        Value::from(s).unwrap()
+    }
+
+    #[test]
+    fn only_pointer_sized_integers_have_a_provenance() {
+        let rules: Rules = toml::from_str("provenance = true\ntag-bits = 2\n").unwrap();
+        assert_eq!(
+            get_provenance_address(&rules, &val("10-8-0000000000001003")),
+            Some(0x1000)
+        );
+        assert_eq!(get_provenance_address(&rules, &val("10-4-00001000")), None);
+        assert_eq!(
+            get_provenance_address(&rules, &val("14-8-0000000000001000")),
+            None
+        );
+        // null page
+        assert_eq!(
+            get_provenance_address(&rules, &val("10-8-0000000000000ffc")),
+            None
+        );
+        assert_eq!(
+            get_provenance_address(&Rules::new(), &val("10-8-0000000000001000")),
+            None
+        );
+    }
+
+    #[test]
+    fn integers_into_live_objects_are_symbolized() {
+        let rules: Rules = toml::from_str("provenance = true\ntag-bits = 2\n").unwrap();
+        let mut tr = get_trace("bench_trace.0-0", Vec::new());
+        tr.objects = vec![Arc::new(Object::get_null()), get_object("a", 0x1000, 16)];
+        let mut cache = HashMap::new();
+        match tr.get_provenance(&val("10-8-0000000000001009"), 0, &mut cache, &rules) {
+            Some(EventData::Pointer { target, offset }) => {
+                assert_eq!(target.id, "a");
+                assert_eq!(offset, 9);
+            }
+            p => panic!("unexpected provenance {:?}", p),
+        }
+        let outside = val("10-8-0000000000001010");
+        assert!(tr.get_provenance(&outside, 0, &mut cache, &rules).is_none());
+    }
+}
@@ -0,0 +1,145 @@
+use object::*;
+use std::collections::HashSet;
+use trace::*;
+use value::*;
+
+// Normalization of the return value and arguments of calls to a function,
+// e.g. `time`, `rand` or `getpid`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct FunctionRule {
+    pub name: String,
+    #[serde(default, rename = "return")]
+    pub ret: bool,
+    #[serde(default)]
+    pub args: Vec<usize>,
+}
+
+// User-defined rules applied while building the symbolic events, e.g.
+//
+// ignore-instructions = [1234]
+// ignore-objects = ["stdout"]
+// provenance = true
+// tag-bits = 2
+//
+// [[function]]
+// name = "printf"
+// args = [1]
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Rules {
+    #[serde(default)]
+    pub function: Vec<FunctionRule>,
+    #[serde(default)]
+    pub ignore_instructions: HashSet<u32>,
+    #[serde(default)]
+    pub ignore_objects: HashSet<String>,
+    // pointer-sized integer values that point into a live object are
+    // symbolized as pointers, see provenance.rs
+    #[serde(default)]
+    pub provenance: bool,
+    // low bits of such integers used as tags (e.g. marked pointers)
+    #[serde(default)]
+    pub tag_bits: u32,
+}
+
+impl Rules {
+    pub fn new() -> Rules {
+        Rules::default()
+    }
+
+    pub fn is_ignored_instruction(&self, id: u32) -> bool {
+        self.ignore_instructions.contains(&id)
+    }
+
+    pub fn is_ignored_object(&self, obj: &Object) -> bool {
+        self.ignore_objects.contains(&obj.id)
+    }
+
+    fn get_function(&self, name: &str) -> Option<&FunctionRule> {
+        self.function.iter().find(|f| f.name == name)
+    }
+
+    // Replaces the normalized return value and arguments of a call event.
+    pub fn normalize_call(&self, data: &mut EventType) {
+        if let EventType::Call {
+            name,
+            value,
+            args,
+        } = data
+        {
+            if let Some(rule) = self.get_function(name) {
+                if rule.ret {
+                    *value = get_normalized();
+                }
+                for i in rule.args.iter() {
+                    if let Some(arg) = args.get_mut(*i) {
+                        *arg = get_normalized();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn get_normalized() -> EventData {
+    EventData::Value {
+        target: String::from("*"),
+        typed: TypedValue::Normalized,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+    use toml;
+
+    fn get_rules(content: &str) -> Rules {
+        toml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn rules_are_parsed() {
+        let rules = get_rules(
+            "ignore-instructions = [12]\n\
+             ignore-objects = [\"stdout\"]\n\
+             [[function]]\n\
+             name = \"time\"\n\
+             return = true\n",
+        );
+        assert!(rules.is_ignored_instruction(12));
+        assert!(!rules.is_ignored_instruction(13));
+        assert!(rules.is_ignored_object(&get_object("stdout", 0x1000, 8)));
+        let is_normalized = |rules: &Rules, name: &str| {
+            let mut data = EventType::Call {
+                name: String::from(name),
+                value: get_value(3),
+                args: Vec::new(),
+            };
+            rules.normalize_call(&mut data);
+            matches!(data, EventType::Call { value, .. } if value == get_normalized())
+        };
+        assert!(is_normalized(&rules, "time"));
+        assert!(!is_normalized(&rules, "printf"));
+        assert!(!is_normalized(&Rules::new(), "time"));
+    }
+
+    #[test]
+    fn calls_are_normalized() {
+        let rules = get_rules("[[function]]\nname = \"printf\"\nargs = [1, 5]\n");
+        let mut data = EventType::Call {
+            name: String::from("printf"),
+            value: get_value(3),
+            args: vec![get_value(1), get_value(2)],
+        };
+        rules.normalize_call(&mut data);
+        assert_eq!(
+            data,
+            EventType::Call {
+                name: String::from("printf"),
+                value: get_value(3),
+                args: vec![get_value(1), get_normalized()],
+            }
+        );
+    }
+}
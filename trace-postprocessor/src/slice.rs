@@ -0,0 +1,261 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use trace::*;
+
+// Backward dynamic slice of a trace, the events are referenced by their
+// index in `SymbolicTrace::events`. An edge (from, to, kind) means that the
+// event `to` depends on the event `from`.
+#[derive(Clone, Debug, Default)]
+pub struct Slice {
+    pub criterion: usize,
+    pub events: BTreeSet<usize>,
+    pub edges: Vec<(usize, usize, &'static str)>,
+}
+
+// Dependencies of every event up to (and including) the criterion:
+// - data: the reads of a thread feed the next write, call or branch of the
+//   thread (the trace has no register def-use information)
+// - rf: a read depends on the last write to the same object and offset
+// - ctrl: an event depends on the last branch of its thread in the same
+//   frame (function and call depth)
+// - sync/fork/join: inter-thread dependencies through locks and threads
+fn get_dependencies(tr: &SymbolicTrace, criterion: usize) -> Vec<Vec<(usize, &'static str)>> {
+    use trace::EventType::*;
+    let mut res = Vec::new();
+    let mut last_write: HashMap<(&String, u64), usize> = HashMap::new();
+    let mut last_object_write: HashMap<&String, usize> = HashMap::new();
+    let mut last_unlock: HashMap<&String, usize> = HashMap::new();
+    let mut last_branch: HashMap<(&String, &String, usize), usize> = HashMap::new();
+    let mut last_event: HashMap<&String, usize> = HashMap::new();
+    let mut pending_reads: HashMap<&String, Vec<usize>> = HashMap::new();
+    let mut forks: HashMap<&String, usize> = HashMap::new();
+    for (i, ev) in tr.events.iter().enumerate().take(criterion + 1) {
+        let mut deps = Vec::new();
+        if let Some(b) = last_branch.get(&(&ev.thread, &ev.func, ev.depth)) {
+            deps.push((*b, "ctrl"));
+        }
+        if !last_event.contains_key(&ev.thread) {
+            if let Some(f) = forks.get(&ev.thread) {
+                deps.push((*f, "fork"));
+            }
+        }
+        match &ev.data {
+            Read { object, offset, .. } => {
+                if let Some(w) = last_write.get(&(&object.id, *offset)) {
+                    deps.push((*w, "rf"));
+                }
+                pending_reads.entry(&ev.thread).or_default().push(i);
+            }
+            Write { object, offset, .. } => {
+                for r in pending_reads.entry(&ev.thread).or_default().drain(..) {
+                    deps.push((r, "data"));
+                }
+                last_write.insert((&object.id, *offset), i);
+                last_object_write.insert(&object.id, i);
+            }
+            Branch { .. } => {
+                for r in pending_reads.entry(&ev.thread).or_default().drain(..) {
+                    deps.push((r, "data"));
+                }
+                last_branch.insert((&ev.thread, &ev.func, ev.depth), i);
+            }
+            Call { args, .. } => {
+                for r in pending_reads.entry(&ev.thread).or_default().drain(..) {
+                    deps.push((r, "data"));
+                }
+                // calls may read from the objects they get pointers to
+                for a in args.iter() {
+                    if let EventData::Pointer { target, .. } = a {
+                        if let Some(w) = last_object_write.get(&target.id) {
+                            deps.push((*w, "rf"));
+                        }
+                    }
+                }
+            }
+            Lock { mutex } => {
+                if let Some(u) = last_unlock.get(&mutex.id) {
+                    deps.push((*u, "sync"));
+                }
+            }
+            Unlock { mutex } => {
+                last_unlock.insert(&mutex.id, i);
+            }
+            Fork { createe, .. } => {
+                forks.insert(createe, i);
+            }
+            Join { joinee, joiner } => {
+                // one of both is the joining thread, the other the joined one
+                let other = if *joinee == ev.thread { joiner } else { joinee };
+                if let Some(l) = last_event.get(other) {
+                    deps.push((*l, "join"));
+                }
+            }
+            DummyEvent => {}
+        }
+        last_event.insert(&ev.thread, i);
+        res.push(deps);
+    }
+    res
+}
+
+pub fn backward_slice(tr: &SymbolicTrace, criterion: usize) -> Slice {
+    let mut res = Slice {
+        criterion,
+        ..Slice::default()
+    };
+    if criterion >= tr.events.len() {
+        return res;
+    }
+    let deps = get_dependencies(tr, criterion);
+    let mut visited = HashSet::new();
+    let mut work = vec![criterion];
+    visited.insert(criterion);
+    while let Some(i) = work.pop() {
+        res.events.insert(i);
+        for (d, kind) in deps[i].iter() {
+            res.edges.push((*d, i, kind));
+            if visited.insert(*d) {
+                work.push(*d);
+            }
+        }
+    }
+    res.edges.sort();
+    res
+}
+
+pub fn get_event_label(ev: &Event) -> String {
+    use trace::EventType::*;
+    match &ev.data {
+        Read { value, .. } if !ev.field.is_empty() => format!("read {} = {}", ev.field, value),
+        Write { value, .. } if !ev.field.is_empty() => format!("write {} = {}", ev.field, value),
+        Read {
+            value,
+            object,
+            offset,
+            ..
+        } => format!("read {}+{} = {}", object.id, offset, value),
+        Write {
+            value,
+            object,
+            offset,
+            ..
+        } => format!("write {}+{} = {}", object.id, offset, value),
+        Branch { target } => format!("branch {}", target),
+        Lock { mutex } => format!("lock {}", mutex.id),
+        Unlock { mutex } => format!("unlock {}", mutex.id),
+        Fork { creator, createe } => format!("fork {} -> {}", creator, createe),
+        Join { joiner, joinee } => format!("join {} <- {}", joiner, joinee),
+        Call { name, value, args } => format!(
+            "call {}({}) = {}",
+            name,
+            args.iter()
+                .map(|a| format!("{}", a))
+                .collect::<Vec<_>>()
+                .join(", "),
+            value
+        ),
+        DummyEvent => String::from("dummy"),
+    }
+}
+
+impl Slice {
+    // Reduced trace, one event of the slice per record.
+    pub fn get_records(&self, tr: &SymbolicTrace) -> Vec<Vec<String>> {
+        self.events
+            .iter()
+            .map(|i| {
+                let ev = &tr.events[*i];
+                vec![
+                    format!("{}", i),
+                    ev.timestamp.clone(),
+                    ev.thread.clone(),
+                    ev.id.clone(),
+                    ev.func.clone(),
+                    format!("{}", ev.depth),
+                    tr.get_location(&ev.id),
+                    get_event_label(ev),
+                ]
+            })
+            .collect()
+    }
+
+    pub fn get_dot(&self, tr: &SymbolicTrace) -> String {
+        let mut res = String::from("digraph {\n");
+        for i in self.events.iter() {
+            let ev = &tr.events[*i];
+            let mut label = format!("{}: {} ID {}", i, ev.thread, ev.id);
+            let loc = tr.get_location(&ev.id);
+            if !loc.is_empty() {
+                label += &format!(" {}", loc);
+            }
+            label += &format!("\\n{}", get_event_label(ev));
+            res += &format!(
+                "    {} [label=\"{}\"{}]\n",
+                i,
+                label.replace('"', "\\\""),
+                if *i == self.criterion { ", shape=box" } else { "" }
+            );
+        }
+        for (from, to, kind) in self.edges.iter() {
+            res += &format!("    {} -> {} [label=\"{}\"]\n", from, to, kind);
+        }
+        res += "}\n";
+        res
+    }
+}
+
+pub fn get_slice_header() -> Vec<&'static str> {
+    vec![
+        "Event",
+        "Timestamp",
+        "Thread",
+        "ID",
+        "Function",
+        "Depth",
+        "Location",
+        "Data",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    #[test]
+    fn slice_follows_reads_data_and_branches() {
+        let x = get_object("x", 0x1000, 8);
+        let y = get_object("y", 0x2000, 8);
+        let z = get_object("z", 0x3000, 8);
+        let tr = get_trace(
+            "bench_trace.0-0",
+            vec![
+                get_write("0", 1, &x, 0, 1),
+                get_write("0", 5, &z, 0, 2),
+                get_read("1", 2, &x, 0, 1),
+                get_branch("1", 3, 7),
+                get_write("1", 4, &y, 0, 1),
+                get_read("1", 6, &y, 0, 1),
+            ],
+        );
+        let slice = backward_slice(&tr, 4);
+        assert_eq!(
+            slice.events.iter().collect::<Vec<_>>(),
+            vec![&0, &2, &3, &4]
+        );
+        assert_eq!(
+            slice.edges,
+            vec![(0, 2, "rf"), (2, 3, "data"), (3, 4, "ctrl")]
+        );
+        assert_eq!(slice.get_records(&tr).len(), 4);
+        let dot = slice.get_dot(&tr);
+        assert!(dot.contains("    4 [label=\"4: 1 ID 4\\n"));
+        assert!(dot.contains("    2 -> 3 [label=\"data\"]\n"));
+    }
+
+    #[test]
+    fn slice_of_an_event_past_the_trace_is_empty() {
+        let tr = get_trace("bench_trace.0-0", vec![get_branch("0", 1, 2)]);
+        assert!(backward_slice(&tr, 1).events.is_empty());
+        assert_eq!(backward_slice(&tr, 0).events.len(), 1);
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u32,
+    pub function: String,
+}
+
+// Source locations of the LLFI instruction IDs, taken from the debug
+// metadata of the instrumented module.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    locations: HashMap<u32, SourceLocation>,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)
+    }
+}
+
+impl SourceMap {
+    pub fn new() -> SourceMap {
+        SourceMap::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    pub fn insert(&mut self, id: u32, loc: SourceLocation) {
+        self.locations.insert(id, loc);
+    }
+
+    pub fn get(&self, id: u32) -> Option<&SourceLocation> {
+        self.locations.get(&id)
+    }
+
+    // `file:line` of an instruction ID, empty if it is unknown.
+    pub fn get_location(&self, id: &str) -> String {
+        id.parse()
+            .ok()
+            .and_then(|i| self.get(i))
+            .map_or(String::new(), |l| format!("{}", l))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use testutils::*;
+
+    fn get_sources() -> SourceMap {
+        let mut sources = SourceMap::new();
+        sources.insert(
+            2,
+            SourceLocation {
+                file: String::from("kmeans.c"),
+                line: 42,
+                function: String::from("main"),
+            },
+        );
+        sources
+    }
+
+    #[test]
+    fn locations_of_instruction_ids() {
+        let sources = get_sources();
+        assert_eq!(sources.get_location("2"), "kmeans.c:42");
+        assert_eq!(sources.get_location("3"), "");
+        assert_eq!(sources.get_location("x"), "");
+        assert!(SourceMap::new().is_empty());
+    }
+
+    #[test]
+    fn only_the_sanitized_llfi_line_has_a_location() {
+        let a = get_object("a", 0x1000, 8);
+        let mut tr = get_trace(
+            "bench_trace.0-0",
+            vec![get_write("0", 1, &a, 0, 1), get_write("0", 2, &a, 0, 1)],
+        );
+        tr.sources = Arc::new(get_sources());
+        let (concrete, sanitized) = tr.get_llfi_trace(u32::MAX).unwrap();
+        assert!(!concrete.contains("Location"));
+        let lines = sanitized.lines().collect::<Vec<_>>();
+        assert!(!lines[0].contains("Location"));
+        assert!(lines[1].ends_with("\tLocation: kmeans.c:42"));
+    }
+}
@@ -0,0 +1,67 @@
+use instruction::*;
+use utils::*;
+
+// Call stack of a thread, maintained from the declared calls and the returns
+// of the raw trace. The entry function of a thread isn't called in the trace,
+// it is at depth 0.
+#[derive(Clone, Debug, Default)]
+pub struct CallStack {
+    frames: Vec<String>,
+}
+
+impl CallStack {
+    pub fn new() -> CallStack {
+        CallStack::default()
+    }
+
+    // Updates the stack after the instruction has been executed.
+    pub fn update(&mut self, inst: &Instruction) {
+        if inst.is_declared_call() {
+            self.frames.push(String::from(get_func_name(&inst.op_name)));
+        } else if inst.is_return() {
+            self.frames.pop();
+        }
+    }
+
+    // Function of the innermost frame, empty in the entry function.
+    pub fn get_function(&self) -> &str {
+        self.frames.last().map_or("", |f| &**f)
+    }
+
+    pub fn get_depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn get_frames(&self) -> &[String] {
+        &self.frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    #[test]
+    fn frames_follow_declared_calls_and_returns() {
+        let mut stack = CallStack::new();
+        assert_eq!(stack.get_function(), "");
+        assert_eq!(stack.get_depth(), 0);
+        stack.update(&get_instruction("1,1,1,call-solve-d,10-4-00000000"));
+        stack.update(&get_instruction("2,1,2,call-step-d,10-4-00000000"));
+        // calls to undeclared functions have no frame in the trace
+        stack.update(&get_instruction(
+            "3,1,3,call-free-u,10-4-00000000,14-8-0000000000001000",
+        ));
+        assert_eq!(stack.get_function(), "step");
+        assert_eq!(stack.get_depth(), 2);
+        assert_eq!(stack.get_frames(), ["solve", "step"]);
+        stack.update(&get_instruction("4,1,4,ret,10-4-00000000"));
+        assert_eq!(stack.get_function(), "solve");
+        stack.update(&get_instruction("5,1,5,ret,10-4-00000000"));
+        // the return of the entry function
+        stack.update(&get_instruction("6,1,6,ret,10-4-00000000"));
+        assert_eq!(stack.get_depth(), 0);
+        assert!(stack.get_frames().is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+use fileio::*;
+use latency::*;
+use outcome::*;
+use std::collections::BTreeMap;
+
+// z value of the 95% confidence level
+const Z_95: f64 = 1.959964;
+
+pub const DEVIATIONS: [&str; 6] = [
+    "no-dev",
+    "within-tol",
+    "data-dev",
+    "addr-dev",
+    "control-dev",
+    "other",
+];
+
+// A proportion with its Wilson score interval.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Rate {
+    pub count: u64,
+    pub total: u64,
+    pub value: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+// Distribution of the detection latencies of the runs with a deviation.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Distribution {
+    pub count: u64,
+    pub min: u64,
+    pub median: u64,
+    pub mean: f64,
+    pub p90: u64,
+    pub max: u64,
+}
+
+// Aggregate of the runs of one benchmark and fault model compared by one
+// method (sanitized, llfi-raw or llfi-symb).
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Summary {
+    pub benchmark: String,
+    pub fault_model: String,
+    pub method: String,
+    pub runs: u64,
+    pub active: u64,
+    pub deviations: BTreeMap<String, u64>,
+    pub outcomes: BTreeMap<String, u64>,
+    // active runs detected by the trace comparison
+    pub trace_coverage: Rate,
+    // active runs with a deviating output, exit code or a crash
+    pub output_coverage: Rate,
+    // trace deviations in runs without an activated fault, e.g. golden runs
+    pub false_positive: Rate,
+    pub latency: BTreeMap<String, Distribution>,
+}
+
+pub fn wilson_interval(count: u64, total: u64, z: f64) -> (f64, f64) {
+    if total == 0 {
+        return (0.0, 1.0);
+    }
+    let n = total as f64;
+    let p = count as f64 / n;
+    let z2 = z * z;
+    let denom = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denom;
+    let half = z / denom * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    ((center - half).max(0.0), (center + half).min(1.0))
+}
+
+// Nearest-rank percentile of the sorted values.
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.max(1) - 1]
+}
+
+impl Distribution {
+    pub fn from(values: &mut [u64]) -> Distribution {
+        if values.is_empty() {
+            return Distribution::default();
+        }
+        values.sort();
+        Distribution {
+            count: values.len() as u64,
+            min: values[0],
+            median: percentile(values, 0.5),
+            mean: values.iter().sum::<u64>() as f64 / values.len() as f64,
+            p90: percentile(values, 0.9),
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+impl Rate {
+    pub fn from(count: u64, total: u64) -> Rate {
+        let (low, high) = wilson_interval(count, total, Z_95);
+        Rate {
+            count,
+            total,
+            value: if total == 0 { 0.0 } else { count as f64 / total as f64 },
+            low,
+            high,
+        }
+    }
+}
+
+fn is_trace_deviation(deviation: &str) -> bool {
+    deviation != "no-dev" && deviation != "within-tol"
+}
+
+fn is_output_deviation(rec: &[String]) -> bool {
+    if rec[get_column("Output-Deviation")] == "true" {
+        return true;
+    }
+    matches!(
+        Outcome::from_name(&rec[get_column("Outcome")]),
+        Some(Outcome::Detected) | Some(Outcome::Crash) | Some(Outcome::Hang)
+    )
+}
+
+#[derive(Default)]
+struct Counter {
+    runs: u64,
+    active: u64,
+    deviations: BTreeMap<String, u64>,
+    outcomes: BTreeMap<String, u64>,
+    trace_detected: u64,
+    output_detected: u64,
+    inactive_deviating: u64,
+    latencies: BTreeMap<&'static str, Vec<u64>>,
+}
+
+pub fn summarize(method: &str, results: &[Vec<String>]) -> Vec<Summary> {
+    let mut counters: BTreeMap<(String, String), Counter> = BTreeMap::new();
+    for rec in results.iter() {
+        let key = (
+            rec[get_column("Benchmark")].clone(),
+            rec[get_column("Fault-model")].clone(),
+        );
+        let cnt = counters.entry(key).or_default();
+        let deviation = &*rec[get_column("Deviation")];
+        let dev_class = if DEVIATIONS.contains(&deviation) {
+            deviation
+        } else {
+            "other"
+        };
+        cnt.runs += 1;
+        *cnt.deviations.entry(String::from(dev_class)).or_insert(0) += 1;
+        let outcome = &rec[get_column("Outcome")];
+        if !outcome.is_empty() {
+            *cnt.outcomes.entry(outcome.clone()).or_insert(0) += 1;
+        }
+        if rec[get_column("Fault-active")] == "true" {
+            cnt.active += 1;
+            if is_trace_deviation(deviation) {
+                cnt.trace_detected += 1;
+            }
+            if is_output_deviation(rec) {
+                cnt.output_detected += 1;
+            }
+        } else if is_trace_deviation(deviation) {
+            cnt.inactive_deviating += 1;
+        }
+        for l in LATENCIES.iter() {
+            if let Ok(v) = rec[get_column(l)].parse::<u64>() {
+                cnt.latencies.entry(l).or_default().push(v);
+            }
+        }
+    }
+    counters
+        .into_iter()
+        .map(|((benchmark, fault_model), mut cnt)| Summary {
+            latency: LATENCIES
+                .iter()
+                .map(|l| {
+                    let values = cnt.latencies.entry(l).or_default();
+                    (String::from(*l), Distribution::from(values))
+                })
+                .collect(),
+            benchmark,
+            fault_model,
+            method: String::from(method),
+            runs: cnt.runs,
+            active: cnt.active,
+            trace_coverage: Rate::from(cnt.trace_detected, cnt.active),
+            output_coverage: Rate::from(cnt.output_detected, cnt.active),
+            false_positive: Rate::from(cnt.inactive_deviating, cnt.runs - cnt.active),
+            deviations: cnt.deviations,
+            outcomes: cnt.outcomes,
+        })
+        .collect()
+}
+
+pub fn get_summary_header() -> Vec<String> {
+    let mut res = vec!["Benchmark", "Fault-model", "Method", "Runs", "Active"];
+    res.extend(DEVIATIONS.iter());
+    res.extend(OUTCOMES.iter().map(|x| x.name()));
+    res.extend(&[
+        "Trace-coverage",
+        "Output-coverage",
+        "False-positive",
+        "Trace-low",
+        "Trace-high",
+        "Output-low",
+        "Output-high",
+        "FP-low",
+        "FP-high",
+    ]);
+    let mut res = res.into_iter().map(String::from).collect::<Vec<_>>();
+    for l in LATENCIES.iter() {
+        for stat in ["median", "mean", "p90", "max"].iter() {
+            res.push(format!("{}-{}", l, stat));
+        }
+    }
+    res
+}
+
+impl Summary {
+    pub fn get_record(&self) -> Vec<String> {
+        let mut res = vec![
+            self.benchmark.clone(),
+            self.fault_model.clone(),
+            self.method.clone(),
+            format!("{}", self.runs),
+            format!("{}", self.active),
+        ];
+        for d in DEVIATIONS.iter() {
+            res.push(format!("{}", self.deviations.get(*d).unwrap_or(&0)));
+        }
+        for o in OUTCOMES.iter() {
+            res.push(format!("{}", self.outcomes.get(o.name()).unwrap_or(&0)));
+        }
+        for rate in [&self.trace_coverage, &self.output_coverage, &self.false_positive].iter() {
+            res.push(format!("{:.6}", rate.value));
+        }
+        for rate in [&self.trace_coverage, &self.output_coverage, &self.false_positive].iter() {
+            res.push(format!("{:.6}", rate.low));
+            res.push(format!("{:.6}", rate.high));
+        }
+        for l in LATENCIES.iter() {
+            let d = &self.latency[*l];
+            res.push(format!("{}", d.median));
+            res.push(format!("{:.2}", d.mean));
+            res.push(format!("{}", d.p90));
+            res.push(format!("{}", d.max));
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_record(fm: &str, active: bool, deviation: &str, outcome: Outcome) -> Vec<String> {
+        let mut rec = vec![String::new(); RESULT_COLUMNS.len()];
+        rec[get_column("Benchmark")] = String::from("bench");
+        rec[get_column("Fault-model")] = String::from(fm);
+        rec[get_column("Fault-active")] = format!("{}", active);
+        rec[get_column("Deviation")] = String::from(deviation);
+        rec[get_column("Output-Deviation")] = format!("{}", outcome == Outcome::Sdc);
+        rec[get_column("Outcome")] = String::from(outcome.name());
+        rec
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn wilson_interval_bounds() {
+        assert_eq!(wilson_interval(0, 0, Z_95), (0.0, 1.0));
+        let (low, high) = wilson_interval(5, 10, Z_95);
+        assert_close(low, 0.236593);
+        assert_close(high, 0.763407);
+        let (low, high) = wilson_interval(0, 10, Z_95);
+        assert_eq!(low, 0.0);
+        assert_close(high, 0.277533);
+        let (low, high) = wilson_interval(10, 10, Z_95);
+        assert_close(low, 0.722467);
+        assert_close(high, 1.0);
+    }
+
+    #[test]
+    fn rate_of_no_runs() {
+        let rate = Rate::from(0, 0);
+        assert_eq!(rate.value, 0.0);
+        assert_eq!((rate.low, rate.high), (0.0, 1.0));
+        assert_eq!(Rate::from(1, 4).value, 0.25);
+    }
+
+    #[test]
+    fn distribution_uses_nearest_rank() {
+        let d = Distribution::from(&mut [7, 1, 3, 10, 5]);
+        assert_eq!((d.count, d.min, d.max), (5, 1, 10));
+        assert_eq!((d.median, d.p90), (5, 10));
+        assert_close(d.mean, 5.2);
+        let d = Distribution::from(&mut (1..=20).collect::<Vec<_>>());
+        assert_eq!((d.median, d.p90), (10, 18));
+        assert_eq!(Distribution::from(&mut []).count, 0);
+    }
+
+    #[test]
+    fn runs_are_summarized_per_fault_model() {
+        let mut results = vec![
+            get_record("1", true, "data-dev", Outcome::Sdc),
+            get_record("1", true, "no-dev", Outcome::Crash),
+            get_record("1", true, "within-tol", Outcome::Benign),
+            get_record("1", false, "control-dev", Outcome::NoActivation),
+            get_record("2", false, "no-dev", Outcome::NoActivation),
+            get_record("2", true, "missing", Outcome::Benign),
+        ];
+        results[0][get_column("Latency-events")] = String::from("4");
+        let res = summarize("sanitized", &results);
+        assert_eq!(res.len(), 2);
+
+        let s = &res[0];
+        assert_eq!((&*s.fault_model, &*s.method), ("1", "sanitized"));
+        assert_eq!((s.runs, s.active), (4, 3));
+        assert_eq!(s.deviations["data-dev"], 1);
+        assert_eq!(s.deviations["control-dev"], 1);
+        assert_eq!(s.outcomes[Outcome::Sdc.name()], 1);
+        assert_eq!((s.trace_coverage.count, s.trace_coverage.total), (1, 3));
+        assert_eq!((s.output_coverage.count, s.output_coverage.total), (2, 3));
+        assert_eq!((s.false_positive.count, s.false_positive.total), (1, 1));
+        assert_eq!(s.latency["Latency-events"].max, 4);
+        assert_eq!(s.latency["Latency-time"].count, 0);
+
+        // unknown deviation classes are counted as other
+        assert_eq!(res[1].deviations["other"], 1);
+        assert_eq!(res[1].get_record().len(), get_summary_header().len());
+    }
+}
@@ -0,0 +1,184 @@
+use cpp_demangle;
+use object::*;
+use std::collections::{HashMap, HashSet};
+
+// string literals that are at most this many bytes apart (alignment padding)
+// are grouped into a single object
+const LITERAL_PADDING: u64 = 16;
+
+#[derive(Clone, Debug, Default)]
+pub struct Symbol {
+    pub name: String,
+    // size of the whole global, e.g. of an array of structs
+    pub size: Option<u64>,
+    // `__thread` or `thread_local` variable
+    pub tls: bool,
+}
+
+// Readable names (and sizes) of global symbols, keyed by the name in the
+// `_globals` file.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolMap {
+    symbols: HashMap<String, Symbol>,
+}
+
+impl SymbolMap {
+    pub fn new() -> SymbolMap {
+        SymbolMap::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn insert(&mut self, symbol: &str, sym: Symbol) {
+        self.symbols.insert(String::from(symbol), sym);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Symbol> {
+        self.symbols.get(symbol)
+    }
+
+    // Names of the thread-local variables after renaming.
+    pub fn get_tls_names(&self) -> HashSet<&str> {
+        self.symbols
+            .values()
+            .filter(|s| s.tls)
+            .map(|s| &*s.name)
+            .collect()
+    }
+}
+
+// Anonymous string literals of clang, `.str`, `.str.1`, ...
+pub fn is_string_literal(name: &str) -> bool {
+    name == ".str" || name.starts_with(".str.")
+}
+
+pub fn demangle(name: &str) -> String {
+    if name.starts_with("_Z") {
+        if let Ok(sym) = cpp_demangle::Symbol::new(name) {
+            return format!("{}", sym);
+        }
+    }
+    String::from(name)
+}
+
+// Renames the globals after the symbol map or their demangled name. The
+// string literals are merged into groups of adjacent literals (`.str`,
+// `.str#2`, ...), an access is then identified by its offset in the group.
+pub fn name_globals(globals: Vec<Object>, symbols: &SymbolMap) -> Vec<Object> {
+    let mut res = Vec::new();
+    let mut literals = Vec::new();
+    for mut obj in globals {
+        if let Some(sym) = symbols.get(&obj.id) {
+            obj.id = sym.name.clone();
+            if let Some(s) = sym.size {
+                obj.size = s;
+            }
+        } else if is_string_literal(&obj.id) {
+            literals.push(obj);
+            continue;
+        } else {
+            obj.id = demangle(&obj.id);
+        }
+        res.push(obj);
+    }
+    literals.sort_by_key(|x| x.address);
+    let mut groups: Vec<Object> = Vec::new();
+    for obj in literals {
+        if let Some(last) = groups.last_mut() {
+            if obj.address <= last.address + last.size + LITERAL_PADDING {
+                last.size = last.size.max(obj.address + obj.size - last.address);
+                continue;
+            }
+        }
+        let mut group = obj;
+        group.id = if groups.is_empty() {
+            String::from(".str")
+        } else {
+            format!(".str#{}", groups.len() + 1)
+        };
+        groups.push(group);
+    }
+    res.extend(groups);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    fn get_global(id: &str, address: u64, size: u64) -> Object {
+        (*get_object(id, address, size)).clone()
+    }
+
+    #[test]
+    fn mangled_names_are_demangled() {
+        assert_eq!(demangle("_ZN2ns7counterE"), "ns::counter");
+        assert_eq!(demangle("counter"), "counter");
+        // not a valid mangling, the name is kept
+        assert_eq!(demangle("_Zinvalid"), "_Zinvalid");
+    }
+
+    #[test]
+    fn globals_are_named_after_the_symbol_map() {
+        let mut symbols = SymbolMap::new();
+        symbols.insert(
+            "points",
+            Symbol {
+                name: String::from("points"),
+                size: Some(96),
+                tls: false,
+            },
+        );
+        symbols.insert(
+            "_ZL5state",
+            Symbol {
+                name: String::from("worker_state"),
+                size: None,
+                tls: true,
+            },
+        );
+        let globals = vec![
+            get_global("points", 0x1000, 8),
+            get_global("_ZL5state", 0x2000, 4),
+            get_global("_ZN2ns7counterE", 0x3000, 8),
+        ];
+        let res = name_globals(globals, &symbols);
+        let names = res.iter().map(|x| (&*x.id, x.size)).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![("points", 96), ("worker_state", 4), ("ns::counter", 8)]
+        );
+        assert_eq!(
+            symbols.get_tls_names().into_iter().collect::<Vec<_>>(),
+            vec!["worker_state"]
+        );
+    }
+
+    #[test]
+    fn adjacent_string_literals_are_grouped() {
+        let globals = vec![
+            get_global(".str.2", 0x4100, 4),
+            get_global(".str", 0x4000, 6),
+            get_global(".str.1", 0x4010, 10),
+            get_global("count", 0x5000, 4),
+        ];
+        let res = name_globals(globals, &SymbolMap::new());
+        let objs = res
+            .iter()
+            .map(|x| (&*x.id, x.address, x.size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            objs,
+            vec![
+                ("count", 0x5000, 4),
+                (".str", 0x4000, 0x1a),
+                (".str#2", 0x4100, 4),
+            ]
+        );
+        assert!(is_string_literal(".str.12"));
+        assert!(!is_string_literal(".string"));
+    }
+}
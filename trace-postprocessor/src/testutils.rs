@@ -0,0 +1,75 @@
+use csv::StringRecord;
+use instruction::*;
+use object::*;
+use std::sync::Arc;
+use trace::*;
+use value::*;
+
+// Instruction of a line of a raw trace, e.g.
+// `1,1,7,call-malloc-u,14-8-0000000000001000,10-8-0000000000000010`
+pub fn get_instruction(line: &str) -> Instruction {
+    try_instruction(line).unwrap()
+}
+
+pub fn try_instruction(line: &str) -> Option<Instruction> {
+    Instruction::from(&StringRecord::from(line.split(',').collect::<Vec<_>>()))
+}
+
+pub fn get_object(id: &str, address: u64, size: u64) -> Arc<Object> {
+    let mut obj = Object::get_null();
+    obj.id = String::from(id);
+    obj.address = address;
+    obj.size = size;
+    Arc::new(obj)
+}
+
+pub fn get_value(val: u128) -> EventData {
+    EventData::Value {
+        target: String::new(),
+        typed: TypedValue::Integer {
+            bits: val,
+            width: 32,
+        },
+    }
+}
+
+pub fn get_read(thread: &str, id: u32, obj: &Arc<Object>, offset: u64, val: u128) -> Event {
+    let data = EventType::Read {
+        value: get_value(val),
+        object: Arc::clone(obj),
+        offset,
+        concrete: String::new(),
+    };
+    Event::new(data, thread, id, "main")
+}
+
+pub fn get_write(thread: &str, id: u32, obj: &Arc<Object>, offset: u64, val: u128) -> Event {
+    let data = EventType::Write {
+        value: get_value(val),
+        object: Arc::clone(obj),
+        offset,
+        concrete: String::new(),
+    };
+    Event::new(data, thread, id, "main")
+}
+
+pub fn get_branch(thread: &str, id: u32, target: u32) -> Event {
+    Event::new(EventType::Branch { target }, thread, id, "main")
+}
+
+// Trace of the events, the timestamps and trace positions are the event
+// indexes. The logical thread `0` is the main thread.
+pub fn get_trace(id: &str, events: Vec<Event>) -> SymbolicTrace {
+    let mut tr = SymbolicTrace::new(id);
+    tr.thread_naming.insert(1, String::from("0"));
+    for (i, mut ev) in events.into_iter().enumerate() {
+        ev.timestamp = format!("{}", i);
+        ev.inst = i;
+        if !tr.thread_naming.values().any(|t| *t == ev.thread) {
+            let n = tr.thread_naming.len() as u64 + 1;
+            tr.thread_naming.insert(n, ev.thread.clone());
+        }
+        tr.events.push(ev);
+    }
+    tr
+}
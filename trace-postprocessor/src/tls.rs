@@ -0,0 +1,207 @@
+use instruction::*;
+use log::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use symbols::*;
+use trace::*;
+use utils::*;
+
+fn get_address(inst: &Instruction) -> u64 {
+    get_hex(&inst.operands[if inst.is_load() { 0 } else { 1 }].value)
+}
+
+// Name of the instance of a thread-local variable of a logical thread.
+pub fn get_tls_name(var: &str, thread: &str) -> String {
+    format!("{}@{}", var, thread)
+}
+
+// Models thread-local variables (marked in the symbol map) as one object per
+// logical thread. The `_globals` file only contains the instances of the
+// main thread, the TLS block of any other thread is at a constant distance
+// to it: the distance is learned from the accesses of a thread to unknown
+// addresses by instructions that accessed a TLS instance before, and only
+// accepted once two different instructions agree on it.
+pub fn add_tls_objects(tr: &mut SymbolicTrace, instructions: &[Instruction], symbols: &SymbolMap) {
+    let names = symbols.get_tls_names();
+    if names.is_empty() || instructions.is_empty() {
+        return;
+    }
+    let root = instructions[0].thread_id;
+    let root_name = tr.thread_naming.get(&root).cloned().unwrap_or_default();
+    let mut vars = Vec::new();
+    for obj in tr.objects.iter_mut() {
+        if names.contains(&*obj.id) {
+            let mut tmp = (**obj).clone();
+            vars.push(tmp.clone());
+            tmp.id = get_tls_name(&tmp.id, &root_name);
+            *obj = Arc::new(tmp);
+        }
+    }
+    if vars.is_empty() {
+        return;
+    }
+
+    // lifetime of the threads in the trace
+    let mut lifetimes: HashMap<u64, (usize, usize)> = HashMap::new();
+    for (pos, inst) in instructions.iter().enumerate() {
+        lifetimes.entry(inst.thread_id).or_insert((pos, pos)).1 = pos;
+    }
+
+    let mut deltas: HashMap<u64, i64> = HashMap::new();
+    deltas.insert(root, 0);
+    // distance of a thread that is not confirmed yet and its instruction ID
+    let mut candidates: HashMap<u64, (i64, u32)> = HashMap::new();
+    // TLS variable and offset accessed by an instruction ID
+    let mut accesses: HashMap<u32, (usize, u64)> = HashMap::new();
+    let mut cache = HashMap::new();
+    for (pos, inst) in instructions.iter().enumerate() {
+        if !(inst.is_load() || inst.is_store()) {
+            continue;
+        }
+        let addr = get_address(inst);
+        match deltas.get(&inst.thread_id) {
+            Some(d) => {
+                let base = (addr as i64 - d) as u64;
+                if let Some(i) = vars
+                    .iter()
+                    .position(|v| base >= v.address && base < v.address + v.size)
+                {
+                    accesses.insert(inst.instruction_id, (i, base - vars[i].address));
+                }
+            }
+            None => {
+                let (i, off) = match accesses.get(&inst.instruction_id) {
+                    Some(x) => *x,
+                    None => continue,
+                };
+                if tr.get_object(addr, pos, &mut cache).is_some() {
+                    continue;
+                }
+                let delta = addr as i64 - (vars[i].address + off) as i64;
+                match candidates.get(&inst.thread_id) {
+                    Some(&(d, id)) if d == delta && id != inst.instruction_id => {}
+                    Some(&(d, _)) if d == delta => continue,
+                    _ => {
+                        if candidates.contains_key(&inst.thread_id) {
+                            debug!("Conflicting TLS distances of thread {}", inst.thread_id);
+                        }
+                        candidates.insert(inst.thread_id, (delta, inst.instruction_id));
+                        continue;
+                    }
+                }
+                deltas.insert(inst.thread_id, delta);
+                let thread = match tr.thread_naming.get(&inst.thread_id) {
+                    Some(t) => t.clone(),
+                    None => continue,
+                };
+                let (start, end) = lifetimes[&inst.thread_id];
+                for v in vars.iter() {
+                    let mut obj = v.clone();
+                    obj.id = get_tls_name(&v.id, &thread);
+                    obj.address = (v.address as i64 + delta) as u64;
+                    obj.hex_addr = to_hex(obj.address);
+                    obj.owner = thread.clone();
+                    obj.construction = start;
+                    obj.destruction = end;
+                    tr.objects.push(Arc::new(obj));
+                }
+                info!("TLS block of {} at distance {}", thread, delta);
+            }
+        }
+    }
+    for (th, _) in lifetimes.iter() {
+        if !deltas.contains_key(th) {
+            debug!("No TLS block found for thread {}", th);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object::*;
+    use testutils::*;
+
+    fn get_load(ts: u64, thread: u64, id: u32, addr: u64) -> Instruction {
+        get_instruction(&format!(
+            "{},{},{},load,10-4-00000000,14-8-{:016x}",
+            ts, thread, id, addr
+        ))
+    }
+
+    fn get_store(ts: u64, thread: u64, id: u32, addr: u64) -> Instruction {
+        get_instruction(&format!(
+            "{},{},{},store,10-4-00000000,10-4-00000001,14-8-{:016x}",
+            ts, thread, id, addr
+        ))
+    }
+
+    fn get_tls_objects(instructions: &[Instruction]) -> Vec<(String, u64)> {
+        let mut symbols = SymbolMap::new();
+        symbols.insert(
+            "tlv",
+            Symbol {
+                name: String::from("tlv"),
+                size: None,
+                tls: true,
+            },
+        );
+        let mut tr = SymbolicTrace::new("bench_trace.0-0");
+        tr.thread_naming.insert(1, String::from("0"));
+        tr.thread_naming.insert(2, String::from("1"));
+        tr.objects.push(Arc::new(Object::get_null()));
+        tr.objects.push(get_object("tlv", 0x1000, 16));
+        tr.objects.push(get_object("g", 0x3000, 8));
+        add_tls_objects(&mut tr, instructions, &symbols);
+        tr.objects
+            .iter()
+            .filter(|x| x.id.starts_with("tlv"))
+            .map(|x| (x.id.clone(), x.address))
+            .collect()
+    }
+
+    #[test]
+    fn tls_blocks_are_confirmed_by_a_second_instruction() {
+        let instructions = vec![
+            get_load(1, 1, 10, 0x1000),
+            get_store(2, 1, 11, 0x1008),
+            get_load(3, 2, 10, 0x9000),
+            // a global, not a TLS access
+            get_load(4, 2, 12, 0x3000),
+            get_store(5, 2, 11, 0x9008),
+        ];
+        assert_eq!(
+            get_tls_objects(&instructions),
+            vec![
+                (String::from("tlv@0"), 0x1000),
+                (String::from("tlv@1"), 0x9000),
+            ]
+        );
+    }
+
+    #[test]
+    fn unconfirmed_tls_blocks_are_not_modeled() {
+        // the same instruction twice
+        let instructions = vec![
+            get_load(1, 1, 10, 0x1000),
+            get_store(2, 1, 11, 0x1008),
+            get_load(3, 2, 10, 0x9000),
+            get_load(4, 2, 10, 0x9000),
+        ];
+        assert_eq!(
+            get_tls_objects(&instructions),
+            vec![(String::from("tlv@0"), 0x1000)]
+        );
+        // two instructions that disagree on the distance
+        let instructions = vec![
+            get_load(1, 1, 10, 0x1000),
+            get_store(2, 1, 11, 0x1008),
+            get_load(3, 2, 10, 0x9000),
+            get_store(4, 2, 11, 0xa008),
+        ];
+        assert_eq!(
+            get_tls_objects(&instructions),
+            vec![(String::from("tlv@0"), 0x1000)]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use error::*;
+use std::collections::HashMap;
+use std::error::Error;
+use trace::*;
+use value::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ToleranceRule {
+    Absolute(f64),
+    Relative(f64),
+    Ulp(u64),
+    // IEEE equality: both zeros are equal, and so are all NaNs
+    Ieee,
+}
+
+// Tolerance rules for floating point values. Rules for an instruction ID take
+// precedence over rules for a function, which take precedence over the global
+// rules. Without any rule floats have to match bit for bit.
+#[derive(Clone, Debug, Default)]
+pub struct Tolerance {
+    global: Vec<ToleranceRule>,
+    by_inst: HashMap<String, Vec<ToleranceRule>>,
+    by_func: HashMap<String, Vec<ToleranceRule>>,
+}
+
+impl ToleranceRule {
+    pub fn from(kind: &str, value: &str) -> Result<ToleranceRule, Box<Error>> {
+        match kind {
+            "absolute" | "abs" => Ok(ToleranceRule::Absolute(value.parse()?)),
+            "relative" | "rel" => Ok(ToleranceRule::Relative(value.parse()?)),
+            "ulp" => Ok(ToleranceRule::Ulp(value.parse()?)),
+            "ieee" => Ok(ToleranceRule::Ieee),
+            _ => Err(Box::new(SanError::new(&format!(
+                "unknown tolerance kind {}",
+                kind
+            )))),
+        }
+    }
+
+    // Apart from the IEEE rule, NaN and infinity never are within a tolerance
+    // of another value (equal values don't get here).
+    fn accepts(&self, v1: &TypedValue, v2: &TypedValue) -> bool {
+        let (f1, f2) = match (v1.as_f64(), v2.as_f64()) {
+            (Some(f1), Some(f2)) => (f1, f2),
+            _ => return false,
+        };
+        if let ToleranceRule::Ieee = self {
+            return (f1.is_nan() && f2.is_nan()) || (f1 == 0.0 && f2 == 0.0);
+        }
+        if !f1.is_finite() || !f2.is_finite() {
+            return false;
+        }
+        let diff = (f1 - f2).abs();
+        match self {
+            ToleranceRule::Absolute(eps) => diff <= *eps,
+            ToleranceRule::Relative(eps) => diff <= *eps * f1.abs().max(f2.abs()),
+            ToleranceRule::Ulp(max) => v1.ulp_distance(v2).is_some_and(|d| d <= *max),
+            ToleranceRule::Ieee => false,
+        }
+    }
+}
+
+impl Tolerance {
+    pub fn new() -> Tolerance {
+        Tolerance::default()
+    }
+
+    pub fn add_rule(
+        &mut self,
+        scope: &str,
+        target: &str,
+        rule: ToleranceRule,
+    ) -> Result<(), Box<Error>> {
+        match scope {
+            "global" => self.global.push(rule),
+            "inst" | "instruction" => self
+                .by_inst
+                .entry(String::from(target))
+                .or_default()
+                .push(rule),
+            "func" | "function" => self
+                .by_func
+                .entry(String::from(target))
+                .or_default()
+                .push(rule),
+            _ => {
+                return Err(Box::new(SanError::new(&format!(
+                    "unknown tolerance scope {}",
+                    scope
+                ))))
+            }
+        }
+        Ok(())
+    }
+
+    fn get_rules(&self, ev: &Event) -> &[ToleranceRule] {
+        if let Some(rules) = self.by_inst.get(&ev.id) {
+            return rules;
+        }
+        if let Some(rules) = self.by_func.get(&ev.func) {
+            return rules;
+        }
+        &self.global
+    }
+
+    // Whether two distinct floats of the given event are close enough to be
+    // considered a match.
+    pub fn is_within(&self, ev: &Event, v1: &TypedValue, v2: &TypedValue) -> bool {
+        if !v1.is_float() || !v2.is_float() {
+            return false;
+        }
+        self.get_rules(ev).iter().any(|r| r.accepts(v1, v2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn double(f: f64) -> TypedValue {
+        TypedValue::Float {
+            bits: f.to_bits() as u128,
+            kind: FloatKind::Double,
+        }
+    }
+
+    fn event(id: u32, func: &str) -> Event {
+        Event::new(EventType::Branch { target: 0 }, "0", id, func)
+    }
+
+    #[test]
+    fn rules_accept_close_values() {
+        let (a, b) = (double(1.0), double(1.0 + 1e-9));
+        assert!(ToleranceRule::Absolute(1e-6).accepts(&a, &b));
+        assert!(!ToleranceRule::Absolute(1e-12).accepts(&a, &b));
+        assert!(ToleranceRule::Relative(1e-6).accepts(&a, &b));
+        assert!(ToleranceRule::Ulp(1).accepts(&double(1.0), &double(1.0 + f64::EPSILON)));
+        assert!(!ToleranceRule::Ulp(1).accepts(&a, &b));
+    }
+
+    #[test]
+    fn rules_reject_nan_and_infinity() {
+        let rules = [
+            ToleranceRule::Absolute(f64::MAX),
+            ToleranceRule::Relative(f64::MAX),
+            ToleranceRule::Ulp(u64::MAX),
+        ];
+        for r in rules.iter() {
+            assert!(!r.accepts(&double(f64::NAN), &double(1.0)), "{:?}", r);
+            assert!(
+                !r.accepts(&double(f64::INFINITY), &double(f64::MAX)),
+                "{:?}",
+                r
+            );
+            assert!(
+                !r.accepts(&double(f64::INFINITY), &double(f64::NEG_INFINITY)),
+                "{:?}",
+                r
+            );
+        }
+    }
+
+    #[test]
+    fn ieee_rule_accepts_zeros_and_nans() {
+        let nan = TypedValue::Float {
+            bits: f64::NAN.to_bits() as u128 | 1,
+            kind: FloatKind::Double,
+        };
+        let rule = ToleranceRule::from("ieee", "").unwrap();
+        assert!(rule.accepts(&double(0.0), &double(-0.0)));
+        assert!(rule.accepts(&double(f64::NAN), &nan));
+        assert!(!rule.accepts(&double(f64::NAN), &double(0.0)));
+        assert!(!rule.accepts(&double(1.0), &double(1.0 + f64::EPSILON)));
+        // without the rule both are deviations
+        let tol = Tolerance::new();
+        assert!(!tol.is_within(&event(1, "f"), &double(0.0), &double(-0.0)));
+    }
+
+    #[test]
+    fn instruction_rules_take_precedence() {
+        let mut tol = Tolerance::new();
+        tol.add_rule("global", "", ToleranceRule::Absolute(1.0))
+            .unwrap();
+        tol.add_rule("func", "f", ToleranceRule::Absolute(0.1))
+            .unwrap();
+        tol.add_rule("inst", "7", ToleranceRule::Absolute(0.01))
+            .unwrap();
+        let (a, b) = (double(1.0), double(1.05));
+        assert!(tol.is_within(&event(1, "g"), &a, &b));
+        assert!(tol.is_within(&event(1, "f"), &a, &b));
+        assert!(!tol.is_within(&event(7, "f"), &a, &b));
+        assert!(tol.add_rule("file", "", ToleranceRule::Ulp(1)).is_err());
+    }
+}
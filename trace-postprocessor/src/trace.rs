@@ -1,8 +1,17 @@
+use crash::*;
 use error::*;
 use fileio::*;
+use heap::*;
 use instruction::*;
+use layout::*;
 use log::*;
+use memcheck::*;
 use object::*;
+use rules::*;
+use source::*;
+use stack::*;
+use symbols::*;
+use tls::*;
 use petgraph::graphmap::DiGraphMap;
 use rayon::prelude::*;
 use std::collections::{HashMap, HashSet};
@@ -11,10 +20,11 @@ use std::fmt;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use utils::*;
+use value::*;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub enum EventData {
-    Value { target: String },
+    Value { target: String, typed: TypedValue },
     Pointer { target: Arc<Object>, offset: u64 },
 }
 
@@ -26,6 +36,12 @@ pub struct Event {
     pub id: String,
     pub inst: usize,
     pub th_cnt: u32,
+    // function the event was executed in and its call depth
+    pub func: String,
+    pub depth: usize,
+    // field path of the accessed offset of reads and writes, if its layout
+    // is known
+    pub field: String,
     op_code: String,
     // necessary for forks and joins
     value: String,
@@ -89,10 +105,29 @@ pub struct SymbolicTrace {
     pub symb_time: Duration,
     pub ret_code: String,
     pub output_hash: String,
+    // the run has been killed (e.g. by the LLFI timeout) before the trace
+    // has been completely written
+    pub truncated: bool,
+    // last load or store per thread, used to locate crashes
+    pub last_accesses: HashMap<String, MemoryAccess>,
+    // loads and stores outside of all live objects
+    pub violations: Vec<MemoryViolation>,
+    pub heap: HeapModel,
+    // source locations of the instruction IDs, shared by all traces
+    pub sources: Arc<SourceMap>,
+    // type layouts of the objects, shared by all traces
+    pub layout: Arc<Layout>,
 }
 
 impl Event {
-    fn from(e_data: EventType, inst: &Instruction, pos: usize, th: &str, cnt: u32) -> Event {
+    fn from(
+        e_data: EventType,
+        inst: &Instruction,
+        pos: usize,
+        th: &str,
+        cnt: u32,
+        stack: &CallStack,
+    ) -> Event {
         Event {
             data: e_data,
             timestamp: inst.timestamp.to_string(),
@@ -100,12 +135,35 @@ impl Event {
             id: inst.instruction_id.to_string().parse().unwrap(),
             inst: pos,
             th_cnt: cnt,
+            func: String::from(stack.get_function()),
+            depth: stack.get_depth(),
+            field: String::new(),
             op_code: inst.op_name.clone(),
             value: inst.value.value.clone(),
         }
     }
 }
 
+#[cfg(test)]
+impl Event {
+    // Event of the instruction `id` of a thread in `func`, for the tests.
+    pub fn new(data: EventType, thread: &str, id: u32, func: &str) -> Event {
+        Event {
+            data,
+            timestamp: String::from("0"),
+            thread: String::from(thread),
+            id: format!("{}", id),
+            inst: 0,
+            th_cnt: 0,
+            func: String::from(func),
+            depth: 0,
+            field: String::new(),
+            op_code: String::new(),
+            value: String::new(),
+        }
+    }
+}
+
 impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let res = format!("{:#?}", self);
@@ -126,8 +184,8 @@ impl fmt::Display for EventData {
                 res.push_str("-");
                 res.push_str(&*long_to_string(off.clone()));
             }
-            Value { target: ref t } => {
-                res.push_str(&*t);
+            Value { typed: ref t, .. } => {
+                res.push_str(&*format!("{}", t));
             }
         }
         return write!(f, "{}", res);
@@ -135,7 +193,7 @@ impl fmt::Display for EventData {
 }
 
 impl SymbolicTrace {
-    fn new(id: &str) -> SymbolicTrace {
+    pub fn new(id: &str) -> SymbolicTrace {
         SymbolicTrace {
             id: String::from(id),
             events: Vec::new(),
@@ -148,33 +206,63 @@ impl SymbolicTrace {
             symb_time: Duration::new(0, 0),
             ret_code: String::new(),
             output_hash: String::new(),
+            truncated: false,
+            last_accesses: HashMap::new(),
+            violations: Vec::new(),
+            heap: HeapModel::new(),
+            sources: Arc::new(SourceMap::new()),
+            layout: Arc::new(Layout::new()),
         }
     }
 
     pub fn drain_content(&mut self) {
         self.objects.clear();
+        self.last_accesses.clear();
+        self.violations.clear();
+        self.heap = HeapModel::new();
         self.events.clear();
         self.thread_naming.clear();
         self.thread_hiearchy.clear();
         self.thread_mapping.clear();
     }
-    pub fn from(fname: &str) -> Result<SymbolicTrace, Box<Error>> {
+    pub fn from(
+        fname: &str,
+        rules: &Rules,
+        layout: &Arc<Layout>,
+        sources: &Arc<SourceMap>,
+        symbols: &SymbolMap,
+    ) -> Result<SymbolicTrace, Box<Error>> {
         let start = Instant::now();
         let f_name = split_f_name(&*fname);
         let mut trace = SymbolicTrace::new(f_name);
+        trace.sources = Arc::clone(sources);
+        trace.layout = Arc::clone(layout);
         //        println!("{:#?}", trace.get_id_info());
         trace.load_injections(fname)?;
 
-        trace.load_ret_code(fname)?;
-        trace.load_output_hash(fname)?;
+        if let Err(e) = trace.load_ret_code(fname) {
+            warn!("No return code for {}, the trace is truncated: {}", fname, e);
+            trace.truncated = true;
+        }
+        let (instructions, truncated) = read_instructions(fname)?;
+        trace.truncated |= truncated;
 
-        let instructions = read_instructions(fname)?;
+        // a run killed by the timeout may not have written its output
+        if let Err(e) = trace.load_output_hash(fname) {
+            if !trace.truncated {
+                return Err(e);
+            }
+            warn!("No output hash for the truncated trace {}: {}", fname, e);
+        }
+        if instructions.is_empty() {
+            return Err(Box::new(SanError::new("empty trace")));
+        }
 
         trace.load_mapping(fname, instructions[0].thread_id)?;
 
-        trace.build_memory(&instructions, fname)?;
+        trace.build_memory(&instructions, fname, symbols)?;
 
-        trace.build_events(&instructions)?;
+        trace.build_events(&instructions, rules, layout)?;
 
         trace.symb_time = start.elapsed();
         //info!("{}", trace.get_llfi_trace()?);
@@ -182,6 +270,17 @@ impl SymbolicTrace {
         Ok(trace)
     }
 
+    // Sanitized value of the LLFI export, pointers are printed as the field
+    // path they point to if the layout of their target is known.
+    fn get_llfi_value(&self, val: &EventData) -> String {
+        match val {
+            EventData::Pointer { target, offset } => self
+                .layout
+                .get_path(target, *offset)
+                .unwrap_or_else(|| format!("{}", val)),
+            _ => format!("{}", val),
+        }
+    }
     fn get_llfi_line(&self, _ev: &Event) -> Result<(String, String), Box<Error>> {
         use EventType::*;
         let mut content = String::new();
@@ -195,7 +294,7 @@ impl SymbolicTrace {
 
         match &_ev.data {
             &Read { value: ref val, .. } | &Write { value: ref val, .. } => {
-                content.push_str(&*format!("{}", val));
+                content.push_str(&*self.get_llfi_value(val));
             }
             &Lock { mutex: ref mtx } | &Unlock { mutex: ref mtx } => {
                 content.push_str(&*mtx.id);
@@ -204,7 +303,7 @@ impl SymbolicTrace {
                 content.push_str(&*_ev.value);
             }
             &Call { value: ref val, .. } => {
-                content.push_str(&*format!("{}", val));
+                content.push_str(&*self.get_llfi_value(val));
             }
             &Branch { target: ref t } => content.push_str(&*int_to_string(t.clone())),
             _ => {
@@ -213,6 +312,10 @@ impl SymbolicTrace {
                 )));
             }
         };
+        // the concrete line keeps the LLFI format
+        if let Some(loc) = _ev.id.parse().ok().and_then(|i| self.sources.get(i)) {
+            content.push_str(&*format!("\tLocation: {}", loc));
+        }
         content.push_str("\n");
         c_content.push_str("\n");
         return Ok((content, c_content));
@@ -223,6 +326,7 @@ impl SymbolicTrace {
         let mut inst_cnt = 0;
         let mut start = false;
         let mut span_cnt = 0;
+        let first_inj = self.get_first_injection();
         for _ev in self.events.iter() {
             inst_cnt += 1;
 
@@ -232,7 +336,7 @@ impl SymbolicTrace {
                 s_content.push_str(&*line.0);
                 span_cnt += 1;
             }
-            if self.is_injected() && self.injection[0].0 < get_long(&*_ev.timestamp) && !start {
+            if first_inj.is_some_and(|ts| ts < get_long(&_ev.timestamp)) && !start {
                 let mut tmp = String::from("#TraceStartInstNumber: ");
                 tmp.push_str(&*sign_int_to_string(inst_cnt));
                 tmp.push_str("\n");
@@ -247,6 +351,11 @@ impl SymbolicTrace {
         }
         return Ok((content, s_content));
     }
+    // `file:line` of an instruction ID, empty without a source map.
+    pub fn get_location(&self, id: &str) -> String {
+        self.sources.get_location(id)
+    }
+
     pub fn get_id_info(&self) -> (String, String, String) {
         let res = self.id.clone();
         let bench = res.split("_trace").collect::<Vec<&str>>()[0];
@@ -283,30 +392,65 @@ impl SymbolicTrace {
         return val;
     }
 
+    fn get_value_data(&self, val: &Value, thread_table: &HashMap<String, String>) -> EventData {
+        let target = self.update_value(val.value.to_string(), thread_table);
+        let mut typed = val.decode();
+        if target != val.value {
+            typed = TypedValue::Thread {
+                name: target.clone(),
+            };
+        }
+        EventData::Value { target, typed }
+    }
+
     pub fn is_injected(&self) -> bool {
         !self.injection.is_empty()
     }
+
+    // The output of a truncated run without output hash is unknown, it
+    // isn't counted as a deviation.
+    pub fn is_output_deviating(&self, golden: &SymbolicTrace) -> bool {
+        !self.output_hash.is_empty() && self.output_hash != golden.output_hash
+    }
+
+    // Timestamp of the earliest injection, the injections are not
+    // necessarily ordered in the _faultinj file.
+    pub fn get_first_injection(&self) -> Option<u64> {
+        self.injection.iter().map(|x| x.0).min()
+    }
+
+    // Index of the injection that precedes the timestamp most closely.
+    pub fn get_preceding_injection(&self, ts: u64) -> Option<usize> {
+        self.injection
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.0 < ts)
+            .max_by_key(|(_, x)| x.0)
+            .map(|(i, _)| i)
+    }
     fn build_memory(
         &mut self,
         instructions: &Vec<Instruction>,
         fname: &str,
+        symbols: &SymbolMap,
     ) -> Result<(), Box<Error>> {
         let mut g_name = String::from(&*fname);
         g_name += "_globals";
-        read_globals(&*g_name, instructions.len(), &mut self.objects)?;
+        read_globals(&*g_name, instructions.len(), &mut self.objects, symbols)?;
         self.load_objects(instructions)?;
+        add_tls_objects(self, instructions, symbols);
         Ok(())
     }
 
     fn load_objects(&mut self, instructions: &Vec<Instruction>) -> Result<(), Box<Error>> {
-        let mut _heap: (Vec<Object>, u32) = (Vec::new(), 0);
-        let mut _idxs: HashMap<u64, u32> = HashMap::new();
+        let mut naming = ObjectNaming::new();
+        let mut frames: HashMap<u64, CallStack> = HashMap::new();
         let mut _stacks: HashMap<u64, Vec<Object>> = HashMap::new();
         let mut _sps: HashMap<u64, Vec<usize>> = HashMap::new();
         let mut last_instr: HashMap<String, usize> = HashMap::new();
 
         for n in self.thread_naming.keys() {
-            _idxs.insert(n.to_owned(), 0);
+            frames.insert(n.to_owned(), CallStack::new());
             _stacks.insert(n.to_owned(), Vec::new());
             _sps.insert(n.to_owned(), Vec::new());
             //last_instr.insert(self.thread_naming.get(n).unwrap().to_owned(), 0);
@@ -328,20 +472,25 @@ impl SymbolicTrace {
                     .ok_or(SanError::new("stack pointer problem"))?;
                 _sp.push(_stacks[&_thread].len());
             }
+            // before the allocation, a realloc may return the same address
+            if _e.is_deallocation() {
+                let owner = self.thread_naming
+                    .get(&_thread)
+                    .ok_or(SanError::new("thread naming problem"))?;
+                if let Some(val) = self.heap.free(_e, pos, owner) {
+                    self.objects.push(Arc::new(val));
+                }
+            }
             if _e.is_allocation() {
-                let _idx = _idxs
+                let owner = self.thread_naming
                     .get(&_thread)
-                    .ok_or(SanError::new("object id problem"))? + 1;
-                let _obj = Object::from(
-                    _e,
-                    _idx,
-                    pos,
-                    true,
-                    self.thread_naming
-                        .get(&_thread)
-                        .ok_or(SanError::new("thread naming problem"))?,
-                ).remove(0);
+                    .ok_or(SanError::new("thread naming problem"))?;
+                let _frames = frames.get(&_thread).ok_or(SanError::new("object id problem"))?;
                 if _e.is_alloca() {
+                    let name = naming.get_name(owner, _frames.get_frames(), _e.instruction_id);
+                    let mut _obj = Object::from(_e, &name, pos, true, owner).remove(0);
+                    _obj.func = String::from(_frames.get_function());
+                    _obj.depth = _frames.get_depth();
                     let _stack = _stacks
                         .get_mut(&_thread)
                         .ok_or(SanError::new("stack pointer problem"))?;
@@ -349,23 +498,9 @@ impl SymbolicTrace {
                     if _e.instruction_id == 374 {
                         //println!("CHECK: {:#?}", _stack);
                     }
-                    _idxs.insert(_thread, _idx);
                 } else {
                     // Heap allocation!
-                    _heap.0.push(_obj);
-                    _heap.1 += 1;
-                }
-            }
-            if _e.is_free() {
-                let mut i = _heap.0.len();
-                while i > 0 {
-                    if _heap.0[i - 1].address == get_hex(&*_e.operands[0].value) {
-                        let mut val = _heap.0.remove(i - 1);
-                        val.update_validity(pos);
-                        self.objects.push(Arc::new(val));
-                        break;
-                    }
-                    i -= 1;
+                    self.heap.allocate(_e, pos, owner, _frames.get_frames());
                 }
             }
             if _e.is_restore_stack() {
@@ -385,6 +520,10 @@ impl SymbolicTrace {
                     i -= 1;
                 }
             }
+            frames
+                .get_mut(&_thread)
+                .ok_or(SanError::new("stack pointer problem"))?
+                .update(_e);
         }
 
         info!("Checking the object stack...");
@@ -406,15 +545,11 @@ impl SymbolicTrace {
             }
         }
 
-        let mut i = 0;
-        let l = _heap.0.len();
-        while i < l {
-            i += 1;
-            let mut obj = _heap
-                .0
-                .pop()
-                .ok_or(SanError::new("couldn't load trace..."))?;
-            obj.update_validity(instructions.len());
+        let remaining = self.heap.drain(instructions.len());
+        if !remaining.is_empty() {
+            warn!("heap is not empty!");
+        }
+        for obj in remaining {
             self.objects.push(Arc::new(obj));
         }
 
@@ -446,9 +581,6 @@ impl SymbolicTrace {
             }
         }
         */
-        if _heap.0.len() > 0 {
-            warn!("heap is not empty!");
-        }
         info!("Finished building the memory model...");
         info!("Number of objects {}", self.objects.len());
 
@@ -470,15 +602,25 @@ impl SymbolicTrace {
     fn get_offset(&self, addr: u64, obj: &Arc<Object>) -> u64 {
         return addr - obj.address;
     }
-    fn build_events(&mut self, instructions: &Vec<Instruction>) -> Result<(), Box<Error>> {
+    fn build_events(
+        &mut self,
+        instructions: &Vec<Instruction>,
+        rules: &Rules,
+        layout: &Layout,
+    ) -> Result<(), Box<Error>> {
         info!("Building symbolic trace...");
         let mut seen = HashSet::new();
         let mut iters = HashMap::new();
         let mut counters = HashMap::new();
         let mut active_map = HashMap::new();
         let mut cache = HashMap::new();
+        let mut naming = ObjectNaming::new();
+        // all identified objects have been created by build_memory
+        let index = ObjectIndex::new(&self.objects);
+        let mut frames: HashMap<&String, CallStack> = HashMap::new();
         for th in self.thread_naming.values() {
             counters.insert(th, 0);
+            frames.insert(th, CallStack::new());
         }
         for (k, v) in self.thread_hiearchy.iter() {
             iters.insert(k, v.iter());
@@ -499,15 +641,15 @@ impl SymbolicTrace {
                     || inst.is_load() || inst.is_store())
                     && inst.get_pointers().len() != 0) || inst.is_main()
                 {
-                    let mut _objs: Vec<Object> = Object::from(
-                        &inst,
-                        cnt.clone(),
-                        pos,
-                        false,
-                        self.thread_naming
-                            .get(&inst.thread_id)
-                            .ok_or(SanError::new("couldn't load trace..."))?,
+                    let name = naming.get_name(
+                        th,
+                        frames
+                            .get(th)
+                            .ok_or(SanError::new("couldn't load trace..."))?
+                            .get_frames(),
+                        inst.instruction_id,
                     );
+                    let mut _objs: Vec<Object> = Object::from(inst, &name, pos, false, th);
 
                     let mut i = 0;
                     let l = _objs.len();
@@ -521,13 +663,34 @@ impl SymbolicTrace {
                     }
                 }
             }
+            if inst.is_load() || inst.is_store() {
+                let address = get_hex(&*inst.operands[if inst.is_load() { 0 } else { 1 }].value);
+                let access = MemoryAccess {
+                    timestamp: inst.timestamp,
+                    pos,
+                    id: inst.instruction_id,
+                    func: frames
+                        .get(th)
+                        .map_or(String::new(), |f| String::from(f.get_function())),
+                    is_write: inst.is_store(),
+                    address,
+                };
+                // the objects guessed for unknown pointers don't make an
+                // access valid
+                if index.get_live_object(address, pos).is_none() {
+                    self.violations.push(MemoryViolation {
+                        thread: th.clone(),
+                        violation: index.check_address(address, pos),
+                        access: access.clone(),
+                    });
+                }
+                self.last_accesses.insert(th.clone(), access);
+            }
             if inst.is_load() {
                 let tmp = get_hex(&*inst.operands[0].value);
                 //                let tmp = u64::from_str_radix(&*inst.operands[0].value.to_string(), 16).ok_or(SanError::new("couldn't load trace..."))?;
                 //                let mut v_tr = None;
-                let mut val = EventData::Value {
-                    target: self.update_value(inst.value.value.to_string(), &active_map),
-                };
+                let mut val = self.get_value_data(&inst.value, &active_map);
                 if inst.value.is_pointer() {
                     let tmp = get_hex(&*inst.value.value);
                     if let Some(obj1) = self.get_object(tmp, pos, &mut cache) {
@@ -539,6 +702,8 @@ impl SymbolicTrace {
                             offset: off,
                         };
                     }
+                } else if let Some(p) = self.get_provenance(&inst.value, pos, &mut cache, rules) {
+                    val = p;
                 }
                 if let Some(obj) = self.get_object(tmp, pos, &mut cache) {
                     let off = self.get_offset(tmp, &obj);
@@ -560,9 +725,7 @@ impl SymbolicTrace {
                 let tmp = get_hex(&*inst.operands[1].value);
                 //let mut v_tr = None;
                 //                let mut off_tr = None;
-                let mut val = EventData::Value {
-                    target: self.update_value(inst.operands[0].value.to_string(), &active_map),
-                };
+                let mut val = self.get_value_data(&inst.operands[0], &active_map);
                 if inst.operands[0].is_pointer() {
                     let tmp = get_hex(&*inst.operands[0].value);
                     if let Some(obj1) = self.get_object(tmp, pos, &mut cache) {
@@ -574,6 +737,8 @@ impl SymbolicTrace {
                         };
                         //                        v_tr = Some(obj1);
                     }
+                } else if let Some(p) = self.get_provenance(&inst.operands[0], pos, &mut cache, rules) {
+                    val = p;
                 }
                 if let Some(obj) = self.get_object(tmp, pos, &mut cache) {
                     let off = self.get_offset(tmp, &obj);
@@ -631,8 +796,10 @@ impl SymbolicTrace {
                         createe: ctt.clone(),
                     };
                 } else {
+                    // the thread creation hasn't been logged completely
+                    warn!("Unmatched fork in thread {}, the trace is truncated", inst.thread_id);
+                    self.truncated = true;
                     break;
-                    info!("Thread ID: {}", inst.thread_id);
                 }
             //                let neigh = symb_tr.get_ordered_neighbours(inst.thread_id);
             //ee = EventType::Fork {
@@ -643,6 +810,8 @@ impl SymbolicTrace {
             } else if inst.is_join() {
                 let tid = get_hex(&*inst.operands[0].value).to_string();
                 if let None = active_map.get(&tid) {
+                    warn!("Unmatched join in thread {}, the trace is truncated", inst.thread_id);
+                    self.truncated = true;
                     break;
                 }
                 ee = EventType::Join {
@@ -677,9 +846,7 @@ impl SymbolicTrace {
                 }
             } else if inst.is_undeclared_call() {
                 let mut arguments = Vec::new();
-                let mut val = EventData::Value {
-                    target: self.update_value(inst.value.value.to_string(), &active_map),
-                };
+                let mut val = self.get_value_data(&inst.value, &active_map);
                 if inst.value.is_pointer() {
                     let tmp = get_hex(&*inst.value.value);
                     if let Some(obj1) = self.get_object(tmp, pos, &mut cache) {
@@ -689,11 +856,11 @@ impl SymbolicTrace {
                             offset: off,
                         };
                     }
+                } else if let Some(p) = self.get_provenance(&inst.value, pos, &mut cache, rules) {
+                    val = p;
                 }
                 for arg in inst.operands.iter() {
-                    let mut val = EventData::Value {
-                        target: self.update_value(arg.value.to_string(), &active_map),
-                    };
+                    let mut val = self.get_value_data(arg, &active_map);
                     if arg.is_pointer() {
                         let tmp = get_hex(&*arg.value);
                         if let Some(obj1) = self.get_object(tmp, pos, &mut cache) {
@@ -703,6 +870,8 @@ impl SymbolicTrace {
                                 offset: off,
                             };
                         }
+                    } else if let Some(p) = self.get_provenance(arg, pos, &mut cache, rules) {
+                        val = p;
                     }
                     arguments.push(val);
                 }
@@ -712,13 +881,25 @@ impl SymbolicTrace {
                     value: val,
                     args: arguments,
                 };
+                rules.normalize_call(&mut ee);
+            }
+            match &ee {
+                EventType::Read { object: ref obj, .. } | EventType::Write { object: ref obj, .. } => {
+                    if rules.is_ignored_object(obj) {
+                        ee = EventType::DummyEvent;
+                    }
+                }
+                _ => {}
+            }
+            if rules.is_ignored_instruction(inst.instruction_id) {
+                ee = EventType::DummyEvent;
             }
             match ee {
                 EventType::DummyEvent => {}
                 _ => {
                     //let th = self.thread_naming.get(&inst.thread_id).ok_or(SanError::new("couldn't load trace..."))?;
                     //let cnt = *counters.get_mut(th).ok_or(SanError::new("couldn't load trace..."))?;
-                    let ev = Event::from(
+                    let mut ev = Event::from(
                         ee,
                         &inst,
                         pos,
@@ -726,11 +907,26 @@ impl SymbolicTrace {
                             .get(&inst.thread_id)
                             .ok_or(SanError::new("couldn't load trace..."))?,
                         cnt as u32,
+                        frames
+                            .get(th)
+                            .ok_or(SanError::new("couldn't load trace..."))?,
                     );
+                    if let EventType::Read { object, offset, .. }
+                    | EventType::Write { object, offset, .. } = &ev.data
+                    {
+                        ev.field = layout.get_path(object, *offset).unwrap_or_default();
+                    }
                     counters.insert(th, cnt + 1);
                     self.events.push(ev);
                 }
             };
+            frames
+                .get_mut(th)
+                .ok_or(SanError::new("couldn't load trace..."))?
+                .update(inst);
+        }
+        if !self.violations.is_empty() {
+            warn!("{} memory violations in {}", self.violations.len(), self.id);
         }
 
         return Ok(());
@@ -0,0 +1,345 @@
+use instruction::Value;
+use std::fmt;
+
+// LLVM type ids as listed in misc/TypeIDs
+pub const HALF_TYPE: u8 = 1;
+pub const FLOAT_TYPE: u8 = 2;
+pub const DOUBLE_TYPE: u8 = 3;
+pub const X86_FP80_TYPE: u8 = 4;
+pub const FP128_TYPE: u8 = 5;
+pub const PPC_FP128_TYPE: u8 = 6;
+pub const INTEGER_TYPE: u8 = 10;
+pub const POINTER_TYPE: u8 = 14;
+pub const VECTOR_TYPE: u8 = 15;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum FloatKind {
+    Half,
+    Single,
+    Double,
+    X86Fp80,
+    Fp128,
+    PpcFp128,
+}
+
+// Floating point values keep their bit pattern so that events stay hashable
+// and totally ordered, the numeric value is only computed on demand.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum TypedValue {
+    Integer { bits: u128, width: u32 },
+    Float { bits: u128, kind: FloatKind },
+    Pointer { address: u64 },
+    Vector { raw: String, size: u64 },
+    Thread { name: String },
+    // placeholder for values normalized by the user rules
+    Normalized,
+    Raw { raw: String, type_id: u8 },
+}
+
+impl TypedValue {
+    pub fn from(val: &Value) -> TypedValue {
+        let raw = || TypedValue::Raw {
+            raw: val.value.clone(),
+            type_id: val.type_id,
+        };
+        if val.type_id == VECTOR_TYPE {
+            return TypedValue::Vector {
+                raw: val.value.clone(),
+                size: val.type_size,
+            };
+        }
+        let bits = match u128::from_str_radix(&val.value, 16) {
+            Ok(b) => b,
+            Err(_) => return raw(),
+        };
+        let kind = match val.type_id {
+            HALF_TYPE => FloatKind::Half,
+            FLOAT_TYPE => FloatKind::Single,
+            DOUBLE_TYPE => FloatKind::Double,
+            X86_FP80_TYPE => FloatKind::X86Fp80,
+            FP128_TYPE => FloatKind::Fp128,
+            PPC_FP128_TYPE => FloatKind::PpcFp128,
+            INTEGER_TYPE => {
+                return TypedValue::Integer {
+                    bits,
+                    width: val.type_size as u32 * 8,
+                };
+            }
+            POINTER_TYPE => {
+                return TypedValue::Pointer {
+                    address: bits as u64,
+                };
+            }
+            _ => return raw(),
+        };
+        TypedValue::Float { bits, kind }
+    }
+
+    pub fn is_float(&self) -> bool {
+        if let TypedValue::Float { .. } = self {
+            return true;
+        }
+        false
+    }
+
+    pub fn unsigned(&self) -> Option<u128> {
+        if let TypedValue::Integer { bits, width } = self {
+            return Some(bits & mask(*width));
+        }
+        None
+    }
+
+    pub fn signed(&self) -> Option<i128> {
+        if let TypedValue::Integer { bits, width } = self {
+            let w = *width;
+            let v = bits & mask(w);
+            if w == 0 || w >= 128 {
+                return Some(v as i128);
+            }
+            if v >> (w - 1) & 1 == 1 {
+                return Some((v | !mask(w)) as i128);
+            }
+            return Some(v as i128);
+        }
+        None
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        if let TypedValue::Float { bits, kind } = self {
+            let b = *bits;
+            let res = match kind {
+                FloatKind::Half => half_to_f64(b as u16),
+                FloatKind::Single => f32::from_bits(b as u32) as f64,
+                FloatKind::Double => f64::from_bits(b as u64),
+                FloatKind::X86Fp80 => x87_to_f64(b & mask(80)),
+                FloatKind::Fp128 => quad_to_f64(b),
+                FloatKind::PpcFp128 => {
+                    f64::from_bits((b >> 64) as u64) + f64::from_bits(b as u64)
+                }
+            };
+            return Some(res);
+        }
+        None
+    }
+
+    // Number of representable values between two floats of the same kind,
+    // formats wider than double are measured in double precision.
+    pub fn ulp_distance(&self, other: &TypedValue) -> Option<u64> {
+        match (self, other) {
+            (TypedValue::Float { bits: b1, kind: k1 }, TypedValue::Float { bits: b2, kind: k2 }) => {
+                if k1 != k2 {
+                    return None;
+                }
+                let (o1, o2) = match k1 {
+                    FloatKind::Half => (ordered(*b1, 16), ordered(*b2, 16)),
+                    FloatKind::Single => (ordered(*b1, 32), ordered(*b2, 32)),
+                    FloatKind::Double => (ordered(*b1, 64), ordered(*b2, 64)),
+                    _ => {
+                        let f1 = self.as_f64().unwrap().to_bits() as u128;
+                        let f2 = other.as_f64().unwrap().to_bits() as u128;
+                        (ordered(f1, 64), ordered(f2, 64))
+                    }
+                };
+                let dist = if o1 > o2 { o1 - o2 } else { o2 - o1 };
+                Some(if dist > u64::MAX as i128 { u64::MAX } else { dist as u64 })
+            }
+            _ => None,
+        }
+    }
+
+    // Equality with the semantics of the value's type: integers only compare
+    // their significant bits, floats their exact bits (a flipped sign of zero
+    // or NaN payload is a deviation unless a tolerance rule accepts it).
+    pub fn is_same(&self, other: &TypedValue) -> bool {
+        match (self, other) {
+            (TypedValue::Integer { .. }, TypedValue::Integer { .. }) => {
+                self.unsigned() == other.unsigned()
+            }
+            _ => self == other,
+        }
+    }
+}
+
+impl fmt::Display for TypedValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypedValue::Integer { width: 1, .. } => write!(f, "{}", self.unsigned().unwrap()),
+            TypedValue::Integer { .. } => write!(f, "{}", self.signed().unwrap()),
+            TypedValue::Float {
+                bits,
+                kind: FloatKind::Single,
+            } => write!(f, "{}", f32::from_bits(*bits as u32)),
+            TypedValue::Float { .. } => write!(f, "{}", self.as_f64().unwrap()),
+            TypedValue::Pointer { address } => write!(f, "0x{:x}", address),
+            TypedValue::Vector { raw, .. } => write!(f, "<{}>", raw),
+            TypedValue::Thread { name } => write!(f, "{}", name),
+            TypedValue::Normalized => write!(f, "*"),
+            TypedValue::Raw { raw, .. } => write!(f, "{}", raw),
+        }
+    }
+}
+
+fn mask(width: u32) -> u128 {
+    if width == 0 || width >= 128 {
+        return !0;
+    }
+    (1u128 << width) - 1
+}
+
+// maps the sign-magnitude encoding onto a monotonic integer scale
+fn ordered(bits: u128, width: u32) -> i128 {
+    let b = bits & mask(width);
+    let sign = 1u128 << (width - 1);
+    if b & sign != 0 {
+        return -((b & !sign) as i128);
+    }
+    b as i128
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits >> 15 == 1 { -1.0 } else { 1.0 };
+    let exp = ((bits >> 10) & 0x1f) as i32;
+    let mant = (bits & 0x3ff) as f64;
+    if exp == 0 {
+        return sign * mant * 2f64.powi(-24);
+    }
+    if exp == 0x1f {
+        if mant == 0.0 {
+            return sign * f64::INFINITY;
+        }
+        return f64::NAN;
+    }
+    sign * (1.0 + mant / 1024.0) * 2f64.powi(exp - 15)
+}
+
+fn x87_to_f64(bits: u128) -> f64 {
+    let sign = if (bits >> 79) & 1 == 1 { -1.0 } else { 1.0 };
+    let exp = ((bits >> 64) & 0x7fff) as i32;
+    let mant = bits as u64;
+    if exp == 0x7fff {
+        if mant << 1 == 0 {
+            return sign * f64::INFINITY;
+        }
+        return f64::NAN;
+    }
+    // the integer bit is explicit, denormals use the minimal exponent
+    let exp = if exp == 0 { 1 } else { exp };
+    sign * (mant as f64) * 2f64.powi(exp - 16383 - 63)
+}
+
+fn quad_to_f64(bits: u128) -> f64 {
+    let sign = if bits >> 127 == 1 { -1.0 } else { 1.0 };
+    let exp = ((bits >> 112) & 0x7fff) as i32;
+    let mant = bits & mask(112);
+    let frac = (mant >> 60) as f64 / 2f64.powi(52);
+    if exp == 0x7fff {
+        if mant == 0 {
+            return sign * f64::INFINITY;
+        }
+        return f64::NAN;
+    }
+    if exp == 0 {
+        return sign * frac * 2f64.powi(-16382);
+    }
+    sign * (1.0 + frac) * 2f64.powi(exp - 16383)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(type_id: u8, raw: &str) -> TypedValue {
+        Value {
+            value: String::from(raw),
+            type_id,
+            type_size: raw.len() as u64 / 2,
+        }
+        .decode()
+    }
+
+    #[test]
+    fn integers_are_sign_extended() {
+        let v = decode(INTEGER_TYPE, "fffffffe");
+        assert_eq!(v.signed(), Some(-2));
+        assert_eq!(v.unsigned(), Some(0xfffffffe));
+        assert_eq!(format!("{}", v), "-2");
+        assert_eq!(format!("{}", decode(INTEGER_TYPE, "01")), "1");
+    }
+
+    #[test]
+    fn floats_are_decoded_per_kind() {
+        assert_eq!(decode(FLOAT_TYPE, "3fc00000").as_f64(), Some(1.5));
+        assert_eq!(decode(DOUBLE_TYPE, "4004000000000000").as_f64(), Some(2.5));
+        assert_eq!(decode(HALF_TYPE, "3c00").as_f64(), Some(1.0));
+        assert_eq!(
+            decode(X86_FP80_TYPE, "3fffc000000000000000").as_f64(),
+            Some(1.5)
+        );
+        assert_eq!(
+            decode(FP128_TYPE, "40000000000000000000000000000000").as_f64(),
+            Some(2.0)
+        );
+        let nan = decode(DOUBLE_TYPE, "7ff8000000000000");
+        assert!(nan.as_f64().unwrap().is_nan());
+    }
+
+    #[test]
+    fn other_types_are_kept() {
+        assert_eq!(
+            decode(POINTER_TYPE, "0000000000001000"),
+            TypedValue::Pointer { address: 0x1000 }
+        );
+        assert_eq!(
+            decode(VECTOR_TYPE, "0102"),
+            TypedValue::Vector {
+                raw: String::from("0102"),
+                size: 2
+            }
+        );
+        assert_eq!(
+            decode(INTEGER_TYPE, "zz"),
+            TypedValue::Raw {
+                raw: String::from("zz"),
+                type_id: INTEGER_TYPE
+            }
+        );
+    }
+
+    #[test]
+    fn float_equality() {
+        let zero = decode(DOUBLE_TYPE, "0000000000000000");
+        let neg_zero = decode(DOUBLE_TYPE, "8000000000000000");
+        let nan1 = decode(DOUBLE_TYPE, "7ff8000000000000");
+        let nan2 = decode(DOUBLE_TYPE, "7ff8000000000001");
+        assert!(!zero.is_same(&neg_zero));
+        assert!(!nan1.is_same(&nan2));
+        assert!(nan1.is_same(&nan1));
+        let one = decode(DOUBLE_TYPE, "3ff0000000000000");
+        let next = decode(DOUBLE_TYPE, "3ff0000000000001");
+        assert!(!one.is_same(&next));
+    }
+
+    #[test]
+    fn floats_have_an_ulp_distance() {
+        let zero = decode(DOUBLE_TYPE, "0000000000000000");
+        let neg_zero = decode(DOUBLE_TYPE, "8000000000000000");
+        let one = decode(DOUBLE_TYPE, "3ff0000000000000");
+        let next = decode(DOUBLE_TYPE, "3ff0000000000001");
+        assert_eq!(one.ulp_distance(&next), Some(1));
+        assert_eq!(zero.ulp_distance(&neg_zero), Some(0));
+        assert_eq!(one.ulp_distance(&decode(FLOAT_TYPE, "3f800000")), None);
+    }
+
+    #[test]
+    fn integers_compare_significant_bits() {
+        let a = TypedValue::Integer {
+            bits: 0x1ff,
+            width: 8,
+        };
+        let b = TypedValue::Integer {
+            bits: 0xff,
+            width: 8,
+        };
+        assert!(a.is_same(&b));
+    }
+}
@@ -0,0 +1,222 @@
+use fileio::*;
+use outcome::*;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use trace::*;
+
+#[derive(Clone, Debug, Default)]
+pub struct VulnerabilityEntry {
+    pub func: String,
+    pub location: String,
+    pub injections: u64,
+    pub outcomes: BTreeMap<Outcome, u64>,
+    // number of runs in which the instruction was the first to deviate
+    pub first_deviation: u64,
+}
+
+// Outcomes per injected LLFI instruction ID over a campaign.
+#[derive(Clone, Debug, Default)]
+pub struct VulnerabilityProfile {
+    entries: BTreeMap<String, VulnerabilityEntry>,
+}
+
+fn get_function(tr: &SymbolicTrace, id: &str) -> Option<String> {
+    tr.events
+        .iter()
+        .find(|ev| ev.id == id)
+        .map(|ev| ev.func.clone())
+}
+
+impl VulnerabilityEntry {
+    pub fn get_failures(&self) -> u64 {
+        [
+            Outcome::Sdc,
+            Outcome::Detected,
+            Outcome::Crash,
+            Outcome::Hang,
+        ]
+        .iter()
+        .map(|o| self.outcomes.get(o).unwrap_or(&0))
+        .sum()
+    }
+
+    fn add(&mut self, other: &VulnerabilityEntry) {
+        self.injections += other.injections;
+        self.first_deviation += other.first_deviation;
+        for (o, n) in other.outcomes.iter() {
+            *self.outcomes.entry(*o).or_insert(0) += n;
+        }
+    }
+
+    fn get_record(&self, key: &[String]) -> Vec<String> {
+        let mut res = key.to_vec();
+        res.push(format!("{}", self.injections));
+        for o in OUTCOMES.iter() {
+            res.push(format!("{}", self.outcomes.get(o).unwrap_or(&0)));
+        }
+        let failures = self.get_failures();
+        res.push(format!("{}", failures));
+        res.push(if self.injections == 0 {
+            String::new()
+        } else {
+            format!("{:.6}", failures as f64 / self.injections as f64)
+        });
+        res.push(format!("{}", self.first_deviation));
+        res
+    }
+}
+
+impl VulnerabilityProfile {
+    pub fn new() -> VulnerabilityProfile {
+        VulnerabilityProfile::default()
+    }
+
+    fn get_entry(
+        &mut self,
+        id: &str,
+        golden: &SymbolicTrace,
+        faulty: &SymbolicTrace,
+    ) -> &mut VulnerabilityEntry {
+        let entry = self.entries.entry(String::from(id)).or_default();
+        if entry.func.is_empty() {
+            // the injected instruction may not have produced an event
+            entry.func = get_function(faulty, id)
+                .or_else(|| get_function(golden, id))
+                .unwrap_or_default();
+            entry.location = faulty.get_location(id);
+        }
+        entry
+    }
+
+    // Adds a faulty run with its result record, see RESULT_COLUMNS.
+    pub fn add_run(&mut self, golden: &SymbolicTrace, faulty: &SymbolicTrace, rec: &[String]) {
+        let outcome = Outcome::from_name(&rec[get_column("Outcome")]);
+        let injected = faulty
+            .injection
+            .iter()
+            .map(|x| x.1.to_string())
+            .collect::<BTreeSet<_>>();
+        for id in injected.iter() {
+            let entry = self.get_entry(id, golden, faulty);
+            entry.injections += 1;
+            if let Some(o) = outcome {
+                *entry.outcomes.entry(o).or_insert(0) += 1;
+            }
+        }
+        let dev_id = &rec[get_column("Deviation-ID")];
+        if !dev_id.is_empty() {
+            self.get_entry(dev_id, golden, faulty).first_deviation += 1;
+        }
+    }
+
+    // Instructions ranked by the number of failures they caused, ties are
+    // broken by how often they were the first deviating event.
+    pub fn get_ranking(&self) -> Vec<Vec<String>> {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(_, e)| (Reverse(e.get_failures()), Reverse(e.first_deviation)));
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, (id, e))| {
+                e.get_record(&[
+                    format!("{}", i + 1),
+                    (*id).clone(),
+                    e.func.clone(),
+                    e.location.clone(),
+                ])
+            })
+            .collect()
+    }
+
+    pub fn get_functions(&self) -> Vec<Vec<String>> {
+        let mut funcs: BTreeMap<&String, (u64, VulnerabilityEntry)> = BTreeMap::new();
+        for e in self.entries.values() {
+            let f = funcs.entry(&e.func).or_default();
+            f.0 += 1;
+            f.1.add(e);
+        }
+        let mut funcs = funcs.into_iter().collect::<Vec<_>>();
+        funcs.sort_by_key(|(_, (_, e))| Reverse(e.get_failures()));
+        funcs
+            .iter()
+            .map(|(func, (n, e))| e.get_record(&[(*func).clone(), format!("{}", n)]))
+            .collect()
+    }
+}
+
+fn get_header(key: &[&'static str]) -> Vec<&'static str> {
+    let mut res = key.to_vec();
+    res.push("Injections");
+    res.extend(OUTCOMES.iter().map(|x| x.name()));
+    res.extend(&["Failures", "Failure-rate", "First-deviation"]);
+    res
+}
+
+pub fn get_ranking_header() -> Vec<&'static str> {
+    get_header(&["Rank", "ID", "Function", "Location"])
+}
+
+pub fn get_functions_header() -> Vec<&'static str> {
+    get_header(&["Function", "Instructions"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testutils::*;
+
+    fn get_run(inst: u32, outcome: Outcome, dev_id: &str) -> (SymbolicTrace, Vec<String>) {
+        let a = get_object("a", 0x1000, 8);
+        let mut tr = get_trace(
+            "bench_trace.1-0",
+            vec![
+                get_write("0", 1, &a, 0, 1),
+                Event::new(EventType::Branch { target: 4 }, "0", 3, "f"),
+            ],
+        );
+        tr.injection.push((0, inst));
+        let mut rec = vec![String::new(); RESULT_COLUMNS.len()];
+        rec[get_column("Outcome")] = String::from(outcome.name());
+        rec[get_column("Deviation-ID")] = String::from(dev_id);
+        (tr, rec)
+    }
+
+    #[test]
+    fn instructions_are_ranked_by_failures() {
+        let golden = get_trace("bench_trace.0-0", Vec::new());
+        let mut profile = VulnerabilityProfile::new();
+        for (inst, outcome, dev_id) in [
+            (1, Outcome::Sdc, "1"),
+            (1, Outcome::Benign, ""),
+            (3, Outcome::Crash, "3"),
+            (3, Outcome::Sdc, "1"),
+        ]
+        .iter()
+        {
+            let (tr, rec) = get_run(*inst, *outcome, dev_id);
+            profile.add_run(&golden, &tr, &rec);
+        }
+        let ranking = profile.get_ranking();
+        assert_eq!(ranking.len(), 2);
+        assert_eq!(ranking[0].len(), get_ranking_header().len());
+        assert_eq!(
+            ranking[0],
+            vec![
+                "1", "3", "f", "", "2", "0", "0", "0", "1", "0", "1", "0", "0", "2", "1.000000",
+                "1"
+            ]
+        );
+        assert_eq!(
+            ranking[1],
+            vec![
+                "2", "1", "main", "", "2", "0", "1", "0", "1", "0", "0", "0", "0", "1", "0.500000",
+                "2"
+            ]
+        );
+        let funcs = profile.get_functions();
+        assert_eq!(funcs.len(), 2);
+        assert_eq!(funcs[0].len(), get_functions_header().len());
+        assert_eq!(funcs[0][..3], ["f", "1", "2"]);
+        assert_eq!(funcs[1][..3], ["main", "1", "2"]);
+    }
+}